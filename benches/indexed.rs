@@ -0,0 +1,63 @@
+//! Compares the access patterns of `IndexedString::at` with indexing a flat `Vec<u16>`.
+//!
+//! Run with `cargo bench --bench indexed`.
+
+use dynstr::{DynamicString, IndexedString};
+use std::hint::black_box;
+use std::time::Instant;
+
+const ROUNDS: usize = 20;
+
+fn rope() -> DynamicString {
+    let piece = DynamicString::new("The quick brown fox jumps over the lazy dog. ");
+    let mut result = DynamicString::empty();
+    for i in 0..2000 {
+        result = result + piece.slice(i % 8, 32);
+    }
+    result
+}
+
+fn report<F: FnMut() -> u64>(name: &str, ops: usize, mut f: F) {
+    black_box(f());
+    let start = Instant::now();
+    for _ in 0..ROUNDS {
+        black_box(f());
+    }
+    let elapsed = start.elapsed();
+    let ns = elapsed.as_nanos() as f64 / (ROUNDS * ops) as f64;
+    println!("{:<32} {:>8.2} ns/op", name, ns);
+}
+
+fn main() {
+    let string = rope();
+    let len = string.len();
+    let indexed = IndexedString::new(string.clone());
+    let vec: Vec<u16> = string.iter().collect();
+
+    // A deterministic pseudo random sequence of indices.
+    let mut seed = 0x2545_f491_u64;
+    let random: Vec<usize> = (0..len)
+        .map(|_| {
+            seed ^= seed << 13;
+            seed ^= seed >> 7;
+            seed ^= seed << 17;
+            (seed % len as u64) as usize
+        })
+        .collect();
+
+    report("sequential Vec<u16>", len, || {
+        (0..len).map(|i| black_box(&vec)[i] as u64).sum()
+    });
+    report("sequential IndexedString::at", len, || {
+        (0..len).map(|i| black_box(&indexed).at(i) as u64).sum()
+    });
+    report("random Vec<u16>", len, || {
+        random.iter().map(|&i| black_box(&vec)[i] as u64).sum()
+    });
+    report("random IndexedString::at", len, || {
+        random
+            .iter()
+            .map(|&i| black_box(&indexed).at(i) as u64)
+            .sum()
+    });
+}
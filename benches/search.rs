@@ -0,0 +1,144 @@
+//! Compares the search algorithms of `PatternFinder` on ASCII and UTF-16 texts, and the KMP
+//! search over `IndexedString::at` that `PatternFinder` used before it read the chunks.
+//!
+//! Run with `cargo bench --bench search`.
+
+use dynstr::{DynamicString, IndexedString, MatchMode, PatternFinder, SearchAlgorithm};
+use std::hint::black_box;
+use std::time::Instant;
+
+const ROUNDS: usize = 20;
+const SAMPLES: usize = 7;
+
+fn rope(piece: &str) -> DynamicString {
+    let piece = DynamicString::new(piece);
+    let mut result = DynamicString::empty();
+    for i in 0..2000 {
+        result = result + piece.slice(i % 8, 32);
+    }
+    result
+}
+
+/// Reports the fastest of several samples, which is less noisy than a single run.
+fn report<F: FnMut() -> u64>(name: &str, units: usize, mut f: F) {
+    black_box(f());
+    let mut best = f64::INFINITY;
+    for _ in 0..SAMPLES {
+        let start = Instant::now();
+        for _ in 0..ROUNDS {
+            black_box(f());
+        }
+        let ns = start.elapsed().as_nanos() as f64 / (ROUNDS * units) as f64;
+        best = best.min(ns);
+    }
+    println!("{:<40} {:>8.3} ns/char", name, best);
+}
+
+/// The original search, which indexed both strings for every comparison and reports the
+/// overlapping matches.
+fn baseline_kmp(text: &DynamicString, pattern: &DynamicString) -> u64 {
+    let text = IndexedString::new(text.clone());
+    let pattern = IndexedString::new(pattern.clone());
+    let (len, ptn_len) = (text.len(), pattern.len());
+
+    let mut lps = vec![0; ptn_len];
+    let mut prefix = 0;
+    let mut i = 1;
+    while i < ptn_len {
+        if pattern.at(i) == pattern.at(prefix) {
+            prefix += 1;
+            lps[i] = prefix;
+            i += 1;
+        } else if prefix != 0 {
+            prefix = lps[prefix - 1];
+        } else {
+            i += 1;
+        }
+    }
+
+    let mut count = 0;
+    let (mut i, mut j) = (0, 0);
+    while i < len {
+        if pattern.at(j) == text.at(i) {
+            j += 1;
+            i += 1;
+        }
+        if j == ptn_len {
+            count += 1;
+            j = lps[j - 1];
+        }
+        if i < len && pattern.at(j) != text.at(i) {
+            if j != 0 {
+                j = lps[j - 1];
+            } else {
+                i += 1;
+            }
+        }
+    }
+    count
+}
+
+fn bench(name: &str, text: &DynamicString, pattern: &str) {
+    let pattern = DynamicString::new(pattern);
+    let len = text.len();
+    let algorithms = [
+        ("kmp", SearchAlgorithm::Kmp),
+        ("horspool", SearchAlgorithm::Horspool),
+        ("byte scan", SearchAlgorithm::ByteScan),
+    ];
+    println!(
+        "{:<40} {:?}",
+        format!("{} auto", name),
+        SearchAlgorithm::select(text, &pattern)
+    );
+    report(&format!("{} kmp baseline", name), len, || {
+        baseline_kmp(black_box(text), &pattern)
+    });
+    for (label, algorithm) in algorithms.iter() {
+        if *algorithm == SearchAlgorithm::ByteScan && pattern.len() != 1 {
+            continue;
+        }
+        report(&format!("{} {}", name, label), len, || {
+            PatternFinder::with_algorithm(
+                black_box(text),
+                &pattern,
+                MatchMode::NonOverlapping,
+                *algorithm,
+            )
+            .count() as u64
+        });
+    }
+}
+
+fn main() {
+    let ascii = rope("The quick brown fox jumps over the lazy dog. ");
+    let utf16 = rope("Съешь же ещё этих мягких французских булок, да выпей чаю. ");
+    let flat_ascii = ascii.clone().flatten();
+    let flat_utf16 = utf16.clone().flatten();
+
+    bench("ascii 1 char", &ascii, "z");
+    bench("ascii 2 chars", &ascii, "ox");
+    bench("ascii 3 chars", &ascii, "fox");
+    bench("ascii 3 chars missing", &ascii, "fog");
+    bench("ascii 4 chars", &ascii, "lazy");
+    bench("ascii 12 chars", &ascii, "jumps over t");
+    bench("ascii missing", &ascii, "quick brown cat");
+    bench("ascii utf16 pattern", &ascii, "oё");
+    bench("utf16 1 char", &utf16, "ё");
+    bench("utf16 2 chars", &utf16, "же");
+    bench("utf16 3 chars", &utf16, "чаю");
+    bench("utf16 4 chars", &utf16, "этих");
+    bench("utf16 12 chars", &utf16, "ещё этих мяг");
+
+    bench("flat ascii 1 char", &flat_ascii, "z");
+    bench("flat ascii 2 chars", &flat_ascii, "ox");
+    bench("flat ascii 3 chars", &flat_ascii, "fox");
+    bench("flat ascii 3 chars missing", &flat_ascii, "fog");
+    bench("flat utf16 2 chars", &flat_utf16, "же");
+    bench("flat utf16 3 chars", &flat_utf16, "чаю");
+    bench("flat ascii 4 chars", &flat_ascii, "lazy");
+    bench("flat ascii 12 chars", &flat_ascii, "jumps over t");
+    bench("flat utf16 1 char", &flat_utf16, "ё");
+    bench("flat utf16 4 chars", &flat_utf16, "этих");
+    bench("flat utf16 12 chars", &flat_utf16, "ещё этих мяг");
+}
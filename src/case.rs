@@ -0,0 +1,163 @@
+use super::case_folding::SIMPLE_CASE_FOLDING;
+use super::{DynamicString, MatchMode, Pattern};
+use std::cmp::Ordering;
+
+/// Decides how the case of the characters is compared.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CaseSensitivity {
+    /// Characters are compared exactly.
+    Sensitive,
+    /// Only the ASCII letters `A-Z` and `a-z` are compared regardless of their case, which
+    /// is faster than `IgnoreCase`.
+    IgnoreAsciiCase,
+    /// Characters are compared after Unicode simple case folding (see
+    /// `CaseSensitivity::fold`), applied to each UTF-16 code unit so characters outside of the
+    /// BMP are compared exactly.
+    IgnoreCase,
+}
+
+impl CaseSensitivity {
+    /// Returns the character that represents the case-insensitive class of `c`.
+    ///
+    /// With `IgnoreCase` this is the simple case folding of the character, from the entries of
+    /// the case folding table of Unicode 14.0.0 (CaseFolding.txt) with the status C or S. The
+    /// folds that map to several characters (status F) and the Turkic ones (status T) are not
+    /// used, so `ß` does not match `ss` and `İ` does not match `i`.
+    /// ```
+    /// use dynstr::CaseSensitivity;
+    /// let fold = |c: char| CaseSensitivity::IgnoreCase.fold(c as u16);
+    /// assert_eq!(fold('Σ'), fold('ς'));
+    /// assert_eq!(fold('\u{212a}'), 'k' as u16);
+    /// // The Cherokee lower case letters fold to the upper case ones.
+    /// assert_eq!(fold('\u{ab70}'), 0x13a0);
+    /// ```
+    #[inline]
+    pub fn fold(self, c: u16) -> u16 {
+        match self {
+            CaseSensitivity::Sensitive => c,
+            CaseSensitivity::IgnoreAsciiCase => fold_ascii(c),
+            CaseSensitivity::IgnoreCase => fold(c),
+        }
+    }
+
+    /// Folds a code point, the ones outside of the BMP are returned as they are.
+    #[inline]
+    pub(crate) fn fold_code_point(self, c: u32) -> u32 {
+        if c <= 0xffff {
+            self.fold(c as u16) as u32
+        } else {
+            c
+        }
+    }
+}
+
+/// Maps the ASCII upper case letters to lower case.
+#[inline(always)]
+pub(crate) fn fold_ascii(c: u16) -> u16 {
+    if (0x41..=0x5a).contains(&c) {
+        c + 0x20
+    } else {
+        c
+    }
+}
+
+/// Returns the simple case folding of a code unit, see `CaseSensitivity::fold`.
+#[inline]
+pub(crate) fn fold(c: u16) -> u16 {
+    if c < 0x80 {
+        return fold_ascii(c);
+    }
+    // The characters of the BMP fold to characters of the BMP.
+    simple_fold(c as u32) as u16
+}
+
+/// Returns the simple case folding of a code point, using a binary search in the table that
+/// is generated from CaseFolding.txt.
+pub(crate) fn simple_fold(c: u32) -> u32 {
+    match SIMPLE_CASE_FOLDING.binary_search_by_key(&c, |&(from, _)| from) {
+        Ok(index) => SIMPLE_CASE_FOLDING[index].1,
+        Err(_) => c,
+    }
+}
+
+impl DynamicString {
+    /// Compares two strings for equality, regardless of the case of their characters.
+    /// ```
+    /// use dynstr::DynamicString;
+    /// let str = DynamicString::new("Straße ΣΊΣΥΦΟΣ");
+    /// assert!(str.eq_ignore_case(&DynamicString::new("STRAßE σίσυφος")));
+    /// assert!(!str.eq_ignore_case(&DynamicString::new("STRASSE σίσυφος")));
+    /// ```
+    #[inline]
+    pub fn eq_ignore_case(&self, other: &DynamicString) -> bool {
+        self.eq_with_case(other, CaseSensitivity::IgnoreCase)
+    }
+
+    /// Compares two strings for equality, regardless of the case of the ASCII letters.
+    #[inline]
+    pub fn eq_ignore_ascii_case(&self, other: &DynamicString) -> bool {
+        self.eq_with_case(other, CaseSensitivity::IgnoreAsciiCase)
+    }
+
+    /// Compares two strings for equality using the given case sensitivity.
+    pub fn eq_with_case(&self, other: &DynamicString, case: CaseSensitivity) -> bool {
+        self.len() == other.len()
+            && self
+                .iter()
+                .zip(other.iter())
+                .all(|(a, b)| a == b || case.fold(a) == case.fold(b))
+    }
+
+    /// Compares two strings by their code units after case folding, so strings that only
+    /// differ by their case are equal.
+    /// ```
+    /// use dynstr::DynamicString;
+    /// use std::cmp::Ordering;
+    /// let a = DynamicString::new("apple");
+    /// assert_eq!(a.cmp_ignore_case(&DynamicString::new("APPLE")), Ordering::Equal);
+    /// assert_eq!(a.cmp_ignore_case(&DynamicString::new("Banana")), Ordering::Less);
+    /// ```
+    #[inline]
+    pub fn cmp_ignore_case(&self, other: &DynamicString) -> Ordering {
+        self.cmp_with_case(other, CaseSensitivity::IgnoreCase)
+    }
+
+    /// Compares two strings by their code units using the given case sensitivity.
+    pub fn cmp_with_case(&self, other: &DynamicString, case: CaseSensitivity) -> Ordering {
+        self.iter()
+            .map(|c| case.fold(c))
+            .cmp(other.iter().map(|c| case.fold(c)))
+    }
+
+    /// Return the index of the first occurrence of the specified value, regardless of the case
+    /// of the characters. Predicates are called with the characters of the string as they
+    /// are.
+    /// ```
+    /// use dynstr::DynamicString;
+    /// let str = DynamicString::new("Hello World");
+    /// assert_eq!(str.index_of_ignore_case("WORLD"), Some(6));
+    /// assert_eq!(str.index_of_ignore_case(&['W', 'L'][..]), Some(2));
+    /// ```
+    pub fn index_of_ignore_case<P: Pattern>(&self, pattern: P) -> Option<usize> {
+        pattern
+            .into_matcher()
+            .into_find_iter_with_case(self, MatchMode::Overlapping, CaseSensitivity::IgnoreCase)
+            .next()
+            .map(|(start, _)| start)
+    }
+
+    /// Determines whether a string begins with the specified value, regardless of the case of
+    /// the characters.
+    /// ```
+    /// use dynstr::DynamicString;
+    /// let str = DynamicString::new("Σίσυφος");
+    /// assert!(str.starts_with_ignore_case("ΣΊ"));
+    /// assert!(str.starts_with_ignore_case('σ'));
+    /// ```
+    pub fn starts_with_ignore_case<P: Pattern>(&self, pattern: P) -> bool {
+        pattern
+            .into_matcher()
+            .match_at_with_case(self, 0, CaseSensitivity::IgnoreCase)
+            .is_some()
+    }
+}
@@ -0,0 +1,106 @@
+use super::encoding::{is_lead_surrogate, is_trail_surrogate};
+use super::methods::clamp_range;
+use super::offsets::OffsetIndex;
+use super::{CodePointMetric, DynamicString, IndexedString, Metric};
+use std::ops::RangeBounds;
+
+/// The CodePointIndex provides random access over a DynamicString by code point index, where
+/// a surrogate pair counts as one code point and an unpaired surrogate as one code point.
+///
+/// Code point indices and UTF-16 offsets are converted to each other in logarithmic time.
+#[derive(Debug, Clone)]
+pub struct CodePointIndex {
+    string: IndexedString,
+    offsets: OffsetIndex,
+}
+
+impl CodePointIndex {
+    /// Creates a new CodePointIndex from a DynamicString.
+    pub fn new(string: DynamicString) -> Self {
+        CodePointIndex::from(IndexedString::new(string))
+    }
+
+    /// Returns the number of code points in the string.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.offsets.total()
+    }
+
+    /// Returns true if the string is empty.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the underlying IndexedString.
+    #[inline]
+    pub fn indexed(&self) -> &IndexedString {
+        &self.string
+    }
+
+    /// Returns the code point at the given code point index, unpaired surrogates are returned
+    /// as-is.
+    /// ```
+    /// use dynstr::{CodePointIndex, DynamicString};
+    /// let index = CodePointIndex::new(DynamicString::new("a😴b"));
+    /// assert_eq!(index.code_point_at(1), Some(0x1f634));
+    /// assert_eq!(index.code_point_at(2), Some('b' as u32));
+    /// assert_eq!(index.code_point_at(3), None);
+    /// ```
+    pub fn code_point_at(&self, index: usize) -> Option<u32> {
+        if index >= self.len() {
+            return None;
+        }
+
+        let unit = self.unit_offset(index)?;
+        let c = self.string.at(unit);
+        match self.string.get(unit + 1) {
+            Some(next) if is_lead_surrogate(c) && is_trail_surrogate(next) => {
+                Some(0x10000 + (((c as u32) & 0x3ff) << 10) + ((next as u32) & 0x3ff))
+            }
+            _ => Some(c as u32),
+        }
+    }
+
+    /// Returns the sub-string in the given range of code point indices, out of bound indices
+    /// are clamped to the number of code points.
+    /// ```
+    /// use dynstr::{CodePointIndex, DynamicString};
+    /// let index = CodePointIndex::new(DynamicString::new("😴😄😃⛔"));
+    /// assert_eq!(index.slice_code_points(1..3), "😄😃");
+    /// ```
+    pub fn slice_code_points<R: RangeBounds<usize>>(&self, range: R) -> DynamicString {
+        let (start, end) = clamp_range(&range, self.len());
+        match (self.unit_offset(start), self.unit_offset(end)) {
+            (Some(start), Some(end)) => self.string.slice(start..end),
+            _ => DynamicString::empty(),
+        }
+    }
+
+    /// Converts a code point index to the UTF-16 offset where the code point starts, the
+    /// number of code points is mapped to the length of the string.
+    #[inline]
+    pub fn unit_offset(&self, code_point: usize) -> Option<usize> {
+        self.offsets.to_unit(&self.string, code_point)
+    }
+
+    /// Converts a UTF-16 offset to the index of the code point that contains it, the length
+    /// of the string is mapped to the number of code points.
+    #[inline]
+    pub fn code_point_offset(&self, unit: usize) -> Option<usize> {
+        self.offsets.to_offset(&self.string, unit)
+    }
+}
+
+impl From<IndexedString> for CodePointIndex {
+    fn from(string: IndexedString) -> Self {
+        let offsets = OffsetIndex::new(&string, CodePointMetric::weight);
+        CodePointIndex { string, offsets }
+    }
+}
+
+impl From<DynamicString> for CodePointIndex {
+    fn from(string: DynamicString) -> Self {
+        CodePointIndex::new(string)
+    }
+}
@@ -0,0 +1,154 @@
+use super::{DynamicString, Summary};
+use std::cmp;
+use std::mem;
+use std::sync::Arc;
+
+/// The two parts of a ConsString and the information about them that is computed when the
+/// node is created, so it never has to be recomputed from the children. The fields are
+/// private so a node can only be created by `DynamicString::cons`, which keeps the tree
+/// balanced.
+pub struct ConsNode {
+    first: DynamicString,
+    second: DynamicString,
+    summary: Summary,
+    one_byte: bool,
+    /// The number of levels of ConsStrings in this node, including itself.
+    height: usize,
+}
+
+impl ConsNode {
+    fn new(first: DynamicString, second: DynamicString) -> Self {
+        ConsNode {
+            summary: first.summary().concat(&second.summary()),
+            one_byte: first.has_one_byte_char() && second.has_one_byte_char(),
+            height: cmp::max(height(&first), height(&second)) + 1,
+            first,
+            second,
+        }
+    }
+
+    /// Returns the first part of the concatenation.
+    #[inline]
+    pub(crate) fn first(&self) -> &DynamicString {
+        &self.first
+    }
+
+    /// Returns the second part of the concatenation.
+    #[inline]
+    pub(crate) fn second(&self) -> &DynamicString {
+        &self.second
+    }
+
+    /// Returns the length of the concatenation.
+    #[inline]
+    pub(crate) fn len(&self) -> usize {
+        self.summary.len()
+    }
+
+    /// Returns the summary of the concatenation.
+    #[inline]
+    pub(crate) fn summary(&self) -> Summary {
+        self.summary
+    }
+
+    /// Returns true if both parts only contain one-byte characters.
+    #[inline]
+    pub(crate) fn has_one_byte_char(&self) -> bool {
+        self.one_byte
+    }
+}
+
+/// Concatenates the two strings into a ConsString where the heights of the two parts of every
+/// node differ by at most one, like in an AVL tree. This keeps the depth of the tree
+/// logarithmic in the number of leaves no matter in which order the strings are concatenated,
+/// and only the nodes on one edge of the taller string are created again.
+pub(crate) fn join(first: DynamicString, second: DynamicString) -> DynamicString {
+    let (first_height, second_height) = (height(&first), height(&second));
+    if first_height > second_height + 1 {
+        let (left, middle) = parts(&first);
+        // The recursion is bounded by the difference of the heights.
+        let middle = join(middle, second);
+        rebalance(left, middle)
+    } else if second_height > first_height + 1 {
+        let (middle, right) = parts(&second);
+        let middle = join(first, middle);
+        rebalance(middle, right)
+    } else {
+        node(first, second)
+    }
+}
+
+/// Creates a node from two balanced strings whose heights differ by at most two, rotating
+/// the taller one if they differ by two.
+fn rebalance(first: DynamicString, second: DynamicString) -> DynamicString {
+    let (first_height, second_height) = (height(&first), height(&second));
+    if second_height > first_height + 1 {
+        let (middle, right) = parts(&second);
+        if height(&middle) <= height(&right) {
+            node(node(first, middle), right)
+        } else {
+            let (middle_first, middle_second) = parts(&middle);
+            node(node(first, middle_first), node(middle_second, right))
+        }
+    } else if first_height > second_height + 1 {
+        let (left, middle) = parts(&first);
+        if height(&middle) <= height(&left) {
+            node(left, node(middle, second))
+        } else {
+            let (middle_first, middle_second) = parts(&middle);
+            node(node(left, middle_first), node(middle_second, second))
+        }
+    } else {
+        node(first, second)
+    }
+}
+
+#[inline]
+fn node(first: DynamicString, second: DynamicString) -> DynamicString {
+    DynamicString::ConsString(Arc::new(ConsNode::new(first, second)))
+}
+
+/// Returns the two parts of a ConsString, which must be taller than a leaf.
+#[inline]
+fn parts(string: &DynamicString) -> (DynamicString, DynamicString) {
+    match string {
+        DynamicString::ConsString(node) => (node.first.clone(), node.second.clone()),
+        _ => unreachable!("a leaf has no parts"),
+    }
+}
+
+/// Returns the height of the string in the tree, every string other than a ConsString is a
+/// leaf.
+#[inline]
+fn height(string: &DynamicString) -> usize {
+    match string {
+        DynamicString::ConsString(node) => node.height,
+        _ => 0,
+    }
+}
+
+impl Drop for ConsNode {
+    fn drop(&mut self) {
+        // Dropping the parts one after the other would recurse as deep as the tree, so the
+        // nodes which are not shared with another string are taken apart here.
+        let mut stack = Vec::new();
+        take_cons(&mut self.first, &mut stack);
+        take_cons(&mut self.second, &mut stack);
+        while let Some(part) = stack.pop() {
+            if let DynamicString::ConsString(node) = part {
+                if let Ok(mut node) = Arc::try_unwrap(node) {
+                    take_cons(&mut node.first, &mut stack);
+                    take_cons(&mut node.second, &mut stack);
+                }
+            }
+        }
+    }
+}
+
+/// Moves `part` to the stack if it is a ConsString.
+#[inline]
+fn take_cons(part: &mut DynamicString, stack: &mut Vec<DynamicString>) {
+    if let DynamicString::ConsString(_) = part {
+        stack.push(mem::replace(part, DynamicString::Empty));
+    }
+}
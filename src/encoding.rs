@@ -0,0 +1,304 @@
+use super::{DynamicString, DynamicStringError};
+use std::char;
+
+impl DynamicString {
+    /// Creates a new string from a UTF-16 encoded slice, returns an error if the slice
+    /// contains an unpaired surrogate.
+    /// ```
+    /// use dynstr::{DynamicString, DynamicStringError};
+    /// let units: Vec<u16> = "Hello 😴".encode_utf16().collect();
+    /// assert_eq!(DynamicString::from_utf16(&units).unwrap(), "Hello 😴");
+    /// assert_eq!(
+    ///     DynamicString::from_utf16(&[0x61, 0xd83d]),
+    ///     Err(DynamicStringError::LoneSurrogate { index: 1 })
+    /// );
+    /// ```
+    pub fn from_utf16(units: &[u16]) -> Result<Self, DynamicStringError> {
+        let mut index = 0;
+        for c in char::decode_utf16(units.iter().copied()) {
+            match c {
+                Ok(c) => index += c.len_utf16(),
+                Err(_) => return Err(DynamicStringError::LoneSurrogate { index }),
+            }
+        }
+
+        Ok(DynamicString::from_units(units.to_vec()))
+    }
+
+    /// Creates a new string from a UTF-16 encoded slice, replacing unpaired surrogates with
+    /// the replacement character (U+FFFD).
+    pub fn from_utf16_lossy(units: &[u16]) -> Self {
+        DynamicString::from_units(replace_lone_surrogates(units.iter().copied()))
+    }
+
+    /// Creates a new string from a sequence of code points, like JavaScript's
+    /// `String.fromCodePoint` surrogate code points are accepted and stored as-is.
+    /// ```
+    /// use dynstr::{DynamicString, DynamicStringError};
+    /// assert_eq!(DynamicString::from_code_points(vec![0x48, 0x69, 0x1f634]).unwrap(), "Hi😴");
+    /// assert_eq!(
+    ///     DynamicString::from_code_points(vec![0x48, 0x110000]),
+    ///     Err(DynamicStringError::InvalidCodePoint { index: 1, code_point: 0x110000 })
+    /// );
+    /// ```
+    pub fn from_code_points<I: IntoIterator<Item = u32>>(
+        code_points: I,
+    ) -> Result<Self, DynamicStringError> {
+        let mut units = Vec::new();
+        for (index, code_point) in code_points.into_iter().enumerate() {
+            match code_point {
+                0..=0xffff => units.push(code_point as u16),
+                0x10000..=0x10ffff => {
+                    let c = code_point - 0x10000;
+                    units.push(0xd800 | (c >> 10) as u16);
+                    units.push(0xdc00 | (c & 0x3ff) as u16);
+                }
+                _ => return Err(DynamicStringError::InvalidCodePoint { index, code_point }),
+            }
+        }
+
+        Ok(DynamicString::from_units(units))
+    }
+
+    /// Converts this string to a `String`, unlike `String::from` this method does not replace
+    /// unpaired surrogates and returns an error containing the index of the first one instead.
+    /// ```
+    /// use dynstr::{DynamicString, DynamicStringError};
+    /// let str = DynamicString::new("a😴");
+    /// assert_eq!(str.try_to_string().unwrap(), "a😴");
+    /// assert_eq!(
+    ///     str.slice(0, 2).try_to_string(),
+    ///     Err(DynamicStringError::LoneSurrogate { index: 1 })
+    /// );
+    /// ```
+    pub fn try_to_string(&self) -> Result<String, DynamicStringError> {
+        let mut result = String::with_capacity(self.len());
+        let mut index = 0;
+        for c in char::decode_utf16(self.iter()) {
+            match c {
+                Ok(c) => {
+                    result.push(c);
+                    index += c.len_utf16();
+                }
+                Err(_) => return Err(DynamicStringError::LoneSurrogate { index }),
+            }
+        }
+
+        Ok(result)
+    }
+
+    /// Encodes this string as WTF-8, which is identical to UTF-8 for well-formed strings but
+    /// can also represent unpaired surrogates, so any string can be restored using
+    /// `from_wtf8`.
+    /// ```
+    /// use dynstr::DynamicString;
+    /// let broken = DynamicString::new("a😴").slice(0, 2);
+    /// let bytes = broken.to_wtf8();
+    /// assert_eq!(bytes, vec![0x61, 0xed, 0xa0, 0xbd]);
+    /// assert_eq!(DynamicString::from_wtf8(&bytes).unwrap(), broken);
+    /// ```
+    pub fn to_wtf8(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(self.len());
+        for c in char::decode_utf16(self.iter()) {
+            match c {
+                Ok(c) => {
+                    let mut buffer = [0; 4];
+                    bytes.extend_from_slice(c.encode_utf8(&mut buffer).as_bytes());
+                }
+                Err(e) => {
+                    let unit = e.unpaired_surrogate();
+                    bytes.push(0xe0 | (unit >> 12) as u8);
+                    bytes.push(0x80 | ((unit >> 6) & 0x3f) as u8);
+                    bytes.push(0x80 | (unit & 0x3f) as u8);
+                }
+            }
+        }
+        bytes
+    }
+
+    /// Decodes a WTF-8 encoded byte slice, returns an error containing the byte offset of the
+    /// first invalid sequence.
+    pub fn from_wtf8(bytes: &[u8]) -> Result<Self, DynamicStringError> {
+        let mut units = Vec::with_capacity(bytes.len());
+        let mut index = 0;
+
+        while index < bytes.len() {
+            let error = DynamicStringError::InvalidWtf8 { index };
+            let first = bytes[index];
+            let (len, min, init) = match first {
+                0x00..=0x7f => (1, 0, first as u32),
+                0xc0..=0xdf => (2, 0x80, (first & 0x1f) as u32),
+                0xe0..=0xef => (3, 0x800, (first & 0x0f) as u32),
+                0xf0..=0xf7 => (4, 0x10000, (first & 0x07) as u32),
+                _ => return Err(error),
+            };
+
+            if index + len > bytes.len() {
+                return Err(error);
+            }
+
+            let mut code_point = init;
+            for &b in &bytes[index + 1..index + len] {
+                if b & 0xc0 != 0x80 {
+                    return Err(error);
+                }
+                code_point = (code_point << 6) | (b & 0x3f) as u32;
+            }
+
+            // Reject overlong encodings and values outside of the Unicode range.
+            if code_point < min || code_point > 0x10ffff {
+                return Err(error);
+            }
+
+            if code_point >= 0x10000 {
+                let c = code_point - 0x10000;
+                units.push(0xd800 | (c >> 10) as u16);
+                units.push(0xdc00 | (c & 0x3ff) as u16);
+            } else {
+                let unit = code_point as u16;
+                // A surrogate pair must be encoded as a single four-byte sequence.
+                if (0xdc00..=0xdfff).contains(&unit)
+                    && matches!(units.last(), Some(0xd800..=0xdbff))
+                {
+                    return Err(error);
+                }
+                units.push(unit);
+            }
+
+            index += len;
+        }
+
+        Ok(DynamicString::from_units(units))
+    }
+
+    /// Returns true if this string does not contain any unpaired surrogates. The number of
+    /// unpaired surrogates is a part of the summary of ConsStrings, so checking a tree does
+    /// not scan its characters.
+    /// ```
+    /// use dynstr::DynamicString;
+    /// let str = DynamicString::new("a😴");
+    /// assert!(str.is_well_formed());
+    /// assert!(!str.slice(0, 2).is_well_formed());
+    /// ```
+    pub fn is_well_formed(&self) -> bool {
+        match self {
+            DynamicString::Empty
+            | DynamicString::SingleOneByteChar(_)
+            | DynamicString::SeqOneByteString(_) => true,
+            DynamicString::SingleTwoByteChar(c) => !is_surrogate(*c),
+            DynamicString::SeqTwoByteString(v) => is_well_formed(v.iter().copied()),
+            DynamicString::SlicedString { .. } | DynamicString::ConsString(_) => {
+                self.summary().is_well_formed()
+            }
+        }
+    }
+
+    /// Returns a new string where all of the unpaired surrogates are replaced with the
+    /// replacement character (U+FFFD), subtrees that are already well-formed are shared with
+    /// the current string.
+    /// ```
+    /// use dynstr::DynamicString;
+    /// let str = DynamicString::new("a😴");
+    /// assert_eq!(str.slice(0, 2).to_well_formed(), "a\u{fffd}");
+    /// ```
+    pub fn to_well_formed(&self) -> Self {
+        if self.is_well_formed() {
+            return self.clone();
+        }
+
+        let mut parts = Vec::new();
+        let mut prev = None;
+        // The strings that are left to repair with the character after each of them, the next
+        // one is at the top.
+        let mut stack = vec![(self, None)];
+        while let Some((node, next)) = stack.pop() {
+            if let DynamicString::ConsString(cons) = node {
+                let summary = cons.summary();
+                if summary.is_well_formed() {
+                    // Neither the first nor the last character of the node is a surrogate that
+                    // could be paired with the characters around it.
+                    prev = node.char_code_at(node.len().wrapping_sub(1)).or(prev);
+                    parts.push(node.clone());
+                } else {
+                    let (first, second) = (cons.first(), cons.second());
+                    stack.push((second, next));
+                    stack.push((first, second.char_code_at(0).or(next)));
+                }
+                continue;
+            }
+
+            // A flat string or a slice is repaired as a whole.
+            let units: Vec<u16> = node.iter().collect();
+            let last = match units.last() {
+                Some(&last) => last,
+                None => continue,
+            };
+            match replace_unpaired(&units, prev, next) {
+                Some(units) => parts.push(DynamicString::from_units(units)),
+                None => parts.push(node.clone()),
+            }
+            prev = Some(last);
+        }
+
+        DynamicString::concat_balanced(parts)
+    }
+}
+
+#[inline(always)]
+fn is_surrogate(c: u16) -> bool {
+    (0xd800..=0xdfff).contains(&c)
+}
+
+#[inline(always)]
+pub(crate) fn is_lead_surrogate(c: u16) -> bool {
+    (0xd800..=0xdbff).contains(&c)
+}
+
+#[inline(always)]
+pub(crate) fn is_trail_surrogate(c: u16) -> bool {
+    (0xdc00..=0xdfff).contains(&c)
+}
+
+#[inline]
+fn is_well_formed<I: Iterator<Item = u16>>(units: I) -> bool {
+    char::decode_utf16(units).all(|c| c.is_ok())
+}
+
+/// Replaces the unpaired surrogates in `units` with the replacement character, `prev` and
+/// `next` are the characters around them. Returns `None` if all of the surrogates are paired.
+fn replace_unpaired(units: &[u16], prev: Option<u16>, next: Option<u16>) -> Option<Vec<u16>> {
+    let unpaired = |i: usize| {
+        let c = units[i];
+        if is_lead_surrogate(c) {
+            let after = units.get(i + 1).copied().or(next);
+            !matches!(after, Some(t) if is_trail_surrogate(t))
+        } else if is_trail_surrogate(c) {
+            let before = if i == 0 { prev } else { Some(units[i - 1]) };
+            !matches!(before, Some(l) if is_lead_surrogate(l))
+        } else {
+            false
+        }
+    };
+
+    if !(0..units.len()).any(unpaired) {
+        return None;
+    }
+    let replaced = (0..units.len()).map(|i| {
+        if unpaired(i) {
+            char::REPLACEMENT_CHARACTER as u16
+        } else {
+            units[i]
+        }
+    });
+    Some(replaced.collect())
+}
+
+fn replace_lone_surrogates<I: Iterator<Item = u16>>(units: I) -> Vec<u16> {
+    let mut result = Vec::with_capacity(units.size_hint().0);
+    for c in char::decode_utf16(units) {
+        let mut buffer = [0; 2];
+        let c = c.unwrap_or(char::REPLACEMENT_CHARACTER);
+        result.extend_from_slice(c.encode_utf16(&mut buffer));
+    }
+    result
+}
@@ -0,0 +1,110 @@
+use std::error::Error;
+use std::fmt;
+
+/// The error type returned by the fallible operations on DynamicStrings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DynamicStringError {
+    /// The index is greater than the length of the string.
+    OutOfBounds { index: usize, len: usize },
+    /// The start of the range is greater than its end.
+    InvalidRange { start: usize, end: usize },
+    /// The index falls in the middle of a surrogate pair.
+    SplitsSurrogate { index: usize },
+    /// The string contains an unpaired surrogate at the given UTF-16 index.
+    LoneSurrogate { index: usize },
+    /// The value at the given position is not a valid Unicode code point.
+    InvalidCodePoint { index: usize, code_point: u32 },
+    /// The byte sequence starting at the given offset is not valid WTF-8.
+    InvalidWtf8 { index: usize },
+}
+
+impl fmt::Display for DynamicStringError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DynamicStringError::OutOfBounds { index, len } => {
+                write!(
+                    f,
+                    "index {} is out of bounds of string of length {}",
+                    index, len
+                )
+            }
+            DynamicStringError::InvalidRange { start, end } => {
+                write!(f, "range start {} is greater than its end {}", start, end)
+            }
+            DynamicStringError::SplitsSurrogate { index } => {
+                write!(f, "index {} is in the middle of a surrogate pair", index)
+            }
+            DynamicStringError::LoneSurrogate { index } => {
+                write!(f, "lone surrogate at index {}", index)
+            }
+            DynamicStringError::InvalidCodePoint { index, code_point } => {
+                write!(f, "invalid code point {:#x} at index {}", code_point, index)
+            }
+            DynamicStringError::InvalidWtf8 { index } => {
+                write!(f, "invalid WTF-8 sequence at byte {}", index)
+            }
+        }
+    }
+}
+
+impl Error for DynamicStringError {}
+
+/// The error type returned when a regular expression can not be compiled, the indices are
+/// positions in the pattern.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RegExpError {
+    /// The flags contain an unknown or a repeated flag.
+    InvalidFlag { flag: char },
+    /// The pattern ends in the middle of an escape, a group, a class or a quantifier.
+    UnexpectedEnd,
+    /// The character at the given index can not appear there.
+    UnexpectedChar { index: usize, c: char },
+    /// A quantifier does not follow anything that can be repeated.
+    NothingToRepeat { index: usize },
+    /// The bounds of a quantifier are out of order.
+    InvalidQuantifier { index: usize },
+    /// The escape sequence at the given index is not valid.
+    InvalidEscape { index: usize },
+    /// The range of a character class is out of order or one of its ends is a class.
+    InvalidClassRange { index: usize },
+    /// The group name at the given index is invalid or declared more than once.
+    InvalidGroupName { index: usize },
+    /// A back reference refers to a group that does not exist.
+    InvalidBackReference { index: usize },
+    /// The pattern exceeds the size limits, because of large repetition counts or deeply
+    /// nested groups.
+    TooLarge,
+}
+
+impl fmt::Display for RegExpError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RegExpError::InvalidFlag { flag } => {
+                write!(f, "invalid regular expression flag '{}'", flag)
+            }
+            RegExpError::UnexpectedEnd => write!(f, "unexpected end of pattern"),
+            RegExpError::UnexpectedChar { index, c } => {
+                write!(f, "unexpected character '{}' at index {}", c, index)
+            }
+            RegExpError::NothingToRepeat { index } => {
+                write!(f, "nothing to repeat at index {}", index)
+            }
+            RegExpError::InvalidQuantifier { index } => {
+                write!(f, "numbers out of order in quantifier at index {}", index)
+            }
+            RegExpError::InvalidEscape { index } => write!(f, "invalid escape at index {}", index),
+            RegExpError::InvalidClassRange { index } => {
+                write!(f, "invalid character class range at index {}", index)
+            }
+            RegExpError::InvalidGroupName { index } => {
+                write!(f, "invalid capture group name at index {}", index)
+            }
+            RegExpError::InvalidBackReference { index } => {
+                write!(f, "invalid back reference at index {}", index)
+            }
+            RegExpError::TooLarge => write!(f, "regular expression is too large"),
+        }
+    }
+}
+
+impl Error for RegExpError {}
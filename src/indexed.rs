@@ -1,13 +1,21 @@
-use super::DynamicString;
+use super::methods::clamp_range;
+use super::{DynamicString, MIN_SLICE_LENGTH};
 use std::cmp;
+use std::ops::{Index, RangeBounds};
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
 
 /// The IndexedString provides an efficient random access over DynamicStrings it should be used
 /// when there are frequent random accesses on the same string.
-#[derive(Debug, Clone)]
+///
+/// The last visited chunk is remembered so sequential accesses usually skip the binary search.
+#[derive(Debug)]
 pub struct IndexedString {
+    string: DynamicString,
     chunks: Vec<(usize, Chunk)>,
     length: usize,
+    /// Index of the chunk that was used in the last call to `at`.
+    last_chunk: AtomicUsize,
 }
 
 #[derive(Debug, Clone)]
@@ -21,115 +29,58 @@ impl IndexedString {
     /// Creates a new IndexedString from a DynamicString.
     pub fn new(string: DynamicString) -> Self {
         let mut chunks = Vec::<(usize, Chunk)>::new();
-        let mut index = 0;
-        let mut to_visit = Vec::<(Box<DynamicString>, Option<usize>)>::new();
-        let mut end: Option<usize> = None;
-        let mut current = Box::new(string);
-        let mut slice_start = 0;
-
-        let get_len = |end: Option<usize>, len: usize| match end {
-            None => len,
-            Some(n) => cmp::min(n, len),
-        };
-
-        loop {
-            // move current to a tmp var so that it can be modified in the match arms.
-            let active = current;
-
-            match active.as_ref() {
-                DynamicString::Empty => {}
-                DynamicString::SingleOneByteChar(c) => {
-                    chunks.push((index, Chunk::Char(*c as u16)));
-                    index += 1;
-                }
-                DynamicString::SingleTwoByteChar(c) => {
-                    chunks.push((index, Chunk::Char(*c)));
-                    index += 1;
-                }
-                DynamicString::SeqOneByteString(v) => {
-                    let len = get_len(end, v.len()) - slice_start;
-                    chunks.push((
-                        index,
-                        Chunk::SeqOneByteString {
-                            vec: v.clone(),
-                            start: slice_start,
-                        },
-                    ));
-                    index += len;
-                }
-                DynamicString::SeqTwoByteString(v) => {
-                    let len = get_len(end, v.len()) - slice_start;
-                    chunks.push((
-                        index,
-                        Chunk::SeqTwoByteString {
-                            vec: v.clone(),
-                            start: slice_start,
-                        },
-                    ));
-                    index += len;
-                }
-                DynamicString::SlicedString {
-                    root,
-                    start,
-                    length,
-                } => {
-                    current = root.clone();
-                    slice_start += start;
-                    end = match end {
-                        None => Some(slice_start + length),
-                        Some(end) => Some(cmp::min(start + end, start + length)),
-                    };
-                    continue;
-                }
-                DynamicString::ConsString { first, second } => {
-                    match end {
-                        None => {
-                            current = first.clone();
-                            to_visit.push((second.clone(), None));
-                        }
-                        Some(n) => {
-                            let first_len = first.len();
-                            if first_len <= slice_start {
-                                // First part is not included.
-                                slice_start -= first_len;
-                                end = Some(n - first_len);
-                                current = second.clone();
-                            } else {
-                                current = first.clone();
-                                if n > first_len {
-                                    to_visit.push((second.clone(), Some(n - first_len)));
-                                }
-                            }
-                        }
-                    }
-                    continue;
-                }
-            }
-
-            match to_visit.pop() {
-                None => {
-                    break;
-                }
-                Some((chunk, e)) => {
-                    current = chunk;
-                    end = e;
-                    slice_start = 0;
-                }
-            };
-        }
-
+        let length = push_chunks(&mut chunks, 0, string.clone());
         IndexedString {
+            string,
             chunks,
-            length: index,
+            length,
+            last_chunk: AtomicUsize::new(0),
         }
     }
 
+    /// Appends the given string to the end of this string, only the chunks of the new string
+    /// are computed so the existing index is reused.
+    /// ```
+    /// use dynstr::{DynamicString, IndexedString};
+    /// let mut indexed = IndexedString::new(DynamicString::new("Hello"));
+    /// indexed.append(DynamicString::new(" world"));
+    /// assert_eq!(indexed.at(6), 'w' as u16);
+    /// assert_eq!(indexed.into_dynamic(), "Hello world");
+    /// ```
+    pub fn append<T: Into<DynamicString>>(&mut self, other: T) {
+        let other = other.into();
+        self.length = push_chunks(&mut self.chunks, self.length, other.clone());
+        self.string = self.string.append(other);
+    }
+
+    /// Returns the DynamicString that this index was built for.
+    #[inline]
+    pub fn into_dynamic(self) -> DynamicString {
+        self.string
+    }
+
     /// Return length of the string.
     #[inline]
     pub fn len(&self) -> usize {
         self.length
     }
 
+    /// Returns true if the string is empty.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.length == 0
+    }
+
+    /// Return the character at the given index, or `None` if it is out of bounds.
+    #[inline]
+    pub fn get(&self, index: usize) -> Option<u16> {
+        if index >= self.length {
+            None
+        } else {
+            Some(self.at(index))
+        }
+    }
+
     /// Return the character at the given index.
     /// # Panics
     /// If the index is greater than or equal to the length.
@@ -142,13 +93,287 @@ impl IndexedString {
         match self.chunks.len() {
             1 => self.chunks[0].1.get(index),
             _ => {
-                let (i, chunk) = &self.chunks[search(&self.chunks, index)];
+                let (i, chunk) = &self.chunks[self.find_chunk(index)];
                 chunk.get(index - i)
             }
         }
     }
+
+    /// Returns the sub-string in the given range, out of bound indices are clamped like
+    /// `DynamicString::substring`. The result is a balanced tree built from the chunks of this
+    /// string so the underlying buffers are shared.
+    /// ```
+    /// use dynstr::{DynamicString, IndexedString};
+    /// let indexed = IndexedString::new(DynamicString::new("Hello") + " world");
+    /// assert_eq!(indexed.slice(4..7), "o w");
+    /// ```
+    pub fn slice<R: RangeBounds<usize>>(&self, range: R) -> DynamicString {
+        let (start, end) = clamp_range(&range, self.length);
+        let mut parts = Vec::new();
+        for (chunk, start, end) in self.chunk_range(start, end) {
+            parts.push(match chunk {
+                Chunk::Char(c) => DynamicString::from_units(vec![*c]),
+                Chunk::SeqOneByteString { vec, start: offset } => {
+                    DynamicString::SeqOneByteString(vec.clone()).slice(offset + start, end - start)
+                }
+                Chunk::SeqTwoByteString { vec, start: offset } => {
+                    DynamicString::SeqTwoByteString(vec.clone()).slice(offset + start, end - start)
+                }
+            });
+        }
+
+        let result = DynamicString::concat_balanced(parts);
+        if result.len() < MIN_SLICE_LENGTH {
+            result.flatten()
+        } else {
+            result
+        }
+    }
+
+    /// Returns an iterator over the characters in the given range, out of bound indices are
+    /// clamped to the length of the string.
+    #[inline]
+    pub fn iter_range<R: RangeBounds<usize>>(&self, range: R) -> IndexedRangeIter<'_> {
+        IndexedRangeIter {
+            chunks: self.chunks_in(range),
+            current: ChunkSlice::OneByte(&[]),
+            index: 0,
+        }
+    }
+
+    /// Returns an iterator over the contiguous pieces of memory that hold the characters in
+    /// the given range, out of bound indices are clamped to the length of the string.
+    /// ```
+    /// use dynstr::{ChunkSlice, DynamicString, IndexedString};
+    /// let str = DynamicString::new("Hello world, ") + DynamicString::new("سلام دنیا");
+    /// let indexed = IndexedString::new(str);
+    /// let chunks: Vec<ChunkSlice> = indexed.chunks_in(6..15).collect();
+    /// assert_eq!(chunks.len(), 2);
+    /// assert_eq!(chunks[0], ChunkSlice::OneByte(b"world, "));
+    /// ```
+    #[inline]
+    pub fn chunks_in<R: RangeBounds<usize>>(&self, range: R) -> Chunks<'_> {
+        let (start, end) = clamp_range(&range, self.length);
+        Chunks(self.chunk_range(start, end))
+    }
+
+    /// Returns an iterator over the chunks that overlap with the range, along with the range
+    /// relative to the start of the chunk.
+    fn chunk_range(&self, start: usize, end: usize) -> ChunkRange<'_> {
+        ChunkRange {
+            string: self,
+            chunk: if start < end {
+                self.find_chunk(start)
+            } else {
+                0
+            },
+            position: start,
+            end,
+        }
+    }
+
+    /// Returns the index of the chunk containing the given index, the last used chunk and
+    /// the one after it are checked before falling back to a binary search.
+    #[inline]
+    fn find_chunk(&self, index: usize) -> usize {
+        let last = self.last_chunk.load(Ordering::Relaxed);
+        // The cached chunk is only written when it changes, so sequential accesses within a
+        // chunk never write to the shared atomic.
+        if self.chunks[last].0 <= index {
+            if index < self.chunk_end(last) {
+                return last;
+            }
+            if last + 1 < self.chunks.len() && index < self.chunk_end(last + 1) {
+                self.last_chunk.store(last + 1, Ordering::Relaxed);
+                return last + 1;
+            }
+        }
+
+        let n = search(&self.chunks, index);
+        self.last_chunk.store(n, Ordering::Relaxed);
+        n
+    }
+
+    /// Returns the index right after the end of the n-th chunk.
+    #[inline(always)]
+    fn chunk_end(&self, n: usize) -> usize {
+        match self.chunks.get(n + 1) {
+            Some((start, _)) => *start,
+            None => self.length,
+        }
+    }
+}
+
+impl Index<usize> for IndexedString {
+    type Output = u16;
+
+    /// Returns a reference to the character at the given index.
+    /// # Panics
+    /// If the index is greater than or equal to the length.
+    #[inline]
+    fn index(&self, index: usize) -> &u16 {
+        if index >= self.length {
+            panic!("Out of bound.")
+        }
+
+        let (i, chunk) = &self.chunks[self.find_chunk(index)];
+        match chunk {
+            Chunk::Char(c) => c,
+            Chunk::SeqOneByteString { vec, start } => {
+                &ONE_BYTE_CHARS[vec[start + index - i] as usize]
+            }
+            Chunk::SeqTwoByteString { vec, start } => &vec[start + index - i],
+        }
+    }
+}
+
+impl PartialEq<DynamicString> for IndexedString {
+    fn eq(&self, other: &DynamicString) -> bool {
+        self.length == other.len() && self.iter_range(..).eq(other.iter())
+    }
+}
+
+impl PartialEq<IndexedString> for DynamicString {
+    #[inline]
+    fn eq(&self, other: &IndexedString) -> bool {
+        other.eq(self)
+    }
+}
+
+impl Clone for IndexedString {
+    fn clone(&self) -> Self {
+        IndexedString {
+            string: self.string.clone(),
+            chunks: self.chunks.clone(),
+            length: self.length,
+            last_chunk: AtomicUsize::new(self.last_chunk.load(Ordering::Relaxed)),
+        }
+    }
+}
+
+/// A contiguous piece of memory holding a part of a DynamicString or an IndexedString.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChunkSlice<'a> {
+    /// A sequence of one-byte characters.
+    OneByte(&'a [u8]),
+    /// A sequence of two-byte (utf-16) characters.
+    TwoByte(&'a [u16]),
+}
+
+impl<'a> ChunkSlice<'a> {
+    /// Returns the number of characters in this chunk.
+    #[inline]
+    pub fn len(&self) -> usize {
+        match self {
+            ChunkSlice::OneByte(s) => s.len(),
+            ChunkSlice::TwoByte(s) => s.len(),
+        }
+    }
+
+    /// Returns true if this chunk has no characters.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the character at the given index.
+    #[inline]
+    pub fn get(&self, index: usize) -> Option<u16> {
+        match self {
+            ChunkSlice::OneByte(s) => s.get(index).map(|&c| c as u16),
+            ChunkSlice::TwoByte(s) => s.get(index).copied(),
+        }
+    }
+}
+
+/// Iterator over the contiguous pieces of memory of an IndexedString in a range, returned by
+/// `IndexedString::chunks_in`.
+#[derive(Debug, Clone)]
+pub struct Chunks<'a>(ChunkRange<'a>);
+
+impl<'a> Iterator for Chunks<'a> {
+    type Item = ChunkSlice<'a>;
+
+    #[inline]
+    fn next(&mut self) -> Option<ChunkSlice<'a>> {
+        self.0.next().map(|(chunk, start, end)| match chunk {
+            Chunk::Char(c) => ChunkSlice::TwoByte(std::slice::from_ref(c)),
+            Chunk::SeqOneByteString { vec, start: offset } => {
+                ChunkSlice::OneByte(&vec[offset + start..offset + end])
+            }
+            Chunk::SeqTwoByteString { vec, start: offset } => {
+                ChunkSlice::TwoByte(&vec[offset + start..offset + end])
+            }
+        })
+    }
+}
+
+/// Iterator over the characters of an IndexedString in a range, returned by
+/// `IndexedString::iter_range`.
+#[derive(Debug, Clone)]
+pub struct IndexedRangeIter<'a> {
+    chunks: Chunks<'a>,
+    current: ChunkSlice<'a>,
+    index: usize,
+}
+
+impl<'a> Iterator for IndexedRangeIter<'a> {
+    type Item = u16;
+
+    #[inline]
+    fn next(&mut self) -> Option<u16> {
+        loop {
+            if let Some(c) = self.current.get(self.index) {
+                self.index += 1;
+                return Some(c);
+            }
+            self.current = self.chunks.next()?;
+            self.index = 0;
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct ChunkRange<'a> {
+    string: &'a IndexedString,
+    /// Index of the chunk containing `position`.
+    chunk: usize,
+    position: usize,
+    end: usize,
+}
+
+impl<'a> Iterator for ChunkRange<'a> {
+    type Item = (&'a Chunk, usize, usize);
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.position < self.end {
+            let n = self.chunk;
+            let (chunk_start, chunk) = &self.string.chunks[n];
+            let chunk_end = cmp::min(self.string.chunk_end(n), self.end);
+            self.chunk += 1;
+            if self.position < chunk_end {
+                let start = self.position - chunk_start;
+                self.position = chunk_end;
+                return Some((chunk, start, chunk_end - chunk_start));
+            }
+        }
+        None
+    }
 }
 
+/// Maps every one-byte character to its two-byte value, used to return references from
+/// `Index::index`.
+static ONE_BYTE_CHARS: [u16; 256] = {
+    let mut table = [0; 256];
+    let mut i = 0;
+    while i < 256 {
+        table[i] = i as u16;
+        i += 1;
+    }
+    table
+};
+
 impl Chunk {
     #[inline]
     pub fn get(&self, index: usize) -> u16 {
@@ -163,12 +388,114 @@ impl Chunk {
     }
 }
 
-#[inline(always)]
-fn search(chunks: &Vec<(usize, Chunk)>, index: usize) -> usize {
-    match chunks.binary_search_by_key(&index, |&(index, _)| index) {
-        Ok(n) => n,
-        Err(n) => n - 1,
+/// Pushes the chunks of the given string to the end of `chunks`, `index` is the position of
+/// the string in the IndexedString. Returns the index right after the pushed chunks.
+fn push_chunks(chunks: &mut Vec<(usize, Chunk)>, mut index: usize, string: DynamicString) -> usize {
+    let mut current = Box::new(string);
+    let mut to_visit = Vec::<(Box<DynamicString>, Option<usize>)>::new();
+    let mut end: Option<usize> = None;
+    let mut slice_start = 0;
+
+    let get_len = |end: Option<usize>, len: usize| match end {
+        None => len,
+        Some(n) => cmp::min(n, len),
+    };
+
+    loop {
+        // move current to a tmp var so that it can be modified in the match arms.
+        let active = current;
+
+        match active.as_ref() {
+            DynamicString::Empty => {}
+            DynamicString::SingleOneByteChar(c) => {
+                chunks.push((index, Chunk::Char(*c as u16)));
+                index += 1;
+            }
+            DynamicString::SingleTwoByteChar(c) => {
+                chunks.push((index, Chunk::Char(*c)));
+                index += 1;
+            }
+            DynamicString::SeqOneByteString(v) => {
+                let len = get_len(end, v.len()) - slice_start;
+                chunks.push((
+                    index,
+                    Chunk::SeqOneByteString {
+                        vec: v.clone(),
+                        start: slice_start,
+                    },
+                ));
+                index += len;
+            }
+            DynamicString::SeqTwoByteString(v) => {
+                let len = get_len(end, v.len()) - slice_start;
+                chunks.push((
+                    index,
+                    Chunk::SeqTwoByteString {
+                        vec: v.clone(),
+                        start: slice_start,
+                    },
+                ));
+                index += len;
+            }
+            DynamicString::SlicedString {
+                root,
+                start,
+                length,
+            } => {
+                current = root.clone();
+                slice_start += start;
+                end = match end {
+                    None => Some(slice_start + length),
+                    Some(end) => Some(cmp::min(start + end, start + length)),
+                };
+                continue;
+            }
+            DynamicString::ConsString(node) => {
+                let (first, second) = (node.first(), node.second());
+                match end {
+                    None => {
+                        current = Box::new(first.clone());
+                        to_visit.push((Box::new(second.clone()), None));
+                    }
+                    Some(n) => {
+                        let first_len = first.len();
+                        if first_len <= slice_start {
+                            // First part is not included.
+                            slice_start -= first_len;
+                            end = Some(n - first_len);
+                            current = Box::new(second.clone());
+                        } else {
+                            current = Box::new(first.clone());
+                            if n > first_len {
+                                to_visit.push((Box::new(second.clone()), Some(n - first_len)));
+                            }
+                        }
+                    }
+                }
+                continue;
+            }
+        }
+
+        match to_visit.pop() {
+            None => {
+                break;
+            }
+            Some((chunk, e)) => {
+                current = chunk;
+                end = e;
+                slice_start = 0;
+            }
+        };
     }
+
+    index
+}
+
+#[inline(always)]
+fn search(chunks: &[(usize, Chunk)], index: usize) -> usize {
+    // The last chunk starting at or before the index, this skips the empty chunks which share
+    // their start with the next chunk.
+    chunks.partition_point(|&(start, _)| start <= index) - 1
 }
 
 impl From<DynamicString> for IndexedString {
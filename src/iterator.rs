@@ -1,4 +1,4 @@
-use super::DynamicString;
+use super::{ChunkSlice, DynamicString};
 use std::cmp;
 
 /// The StringIterator can be used to iterate over characters in a DynamicString.
@@ -25,7 +25,6 @@ impl DynamicStringIterator {
         match self.to_visit.pop() {
             None => {
                 self.active_chunk = None;
-                return;
             }
             Some((chunk, end)) => {
                 self.active_chunk = Some(chunk);
@@ -37,8 +36,8 @@ impl DynamicStringIterator {
 
     /// Consume the current slice chunk and compute `end`.
     #[inline(always)]
-    fn advance_slice_chunk(&mut self, root: &Box<DynamicString>, start: usize, length: usize) {
-        self.active_chunk = Some(root.clone());
+    fn advance_slice_chunk(&mut self, root: &DynamicString, start: usize, length: usize) {
+        self.active_chunk = Some(Box::new(root.clone()));
         self.chunk_index += start;
         self.end = match self.end {
             None => Some(self.chunk_index + length),
@@ -48,11 +47,11 @@ impl DynamicStringIterator {
 
     /// Consume the current cons chunk compute `end` for the second part.
     #[inline(always)]
-    fn advance_cons_chunk(&mut self, first: &Box<DynamicString>, second: &Box<DynamicString>) {
+    fn advance_cons_chunk(&mut self, first: &DynamicString, second: &DynamicString) {
         match self.end {
             None => {
-                self.active_chunk = Some(first.clone());
-                self.to_visit.push((second.clone(), None));
+                self.active_chunk = Some(Box::new(first.clone()));
+                self.to_visit.push((Box::new(second.clone()), None));
                 debug_assert!(self.chunk_index == 0);
             }
             Some(end) => {
@@ -61,11 +60,12 @@ impl DynamicStringIterator {
                     // First part is not included.
                     self.chunk_index -= first_len;
                     self.end = Some(end - first_len);
-                    self.active_chunk = Some(second.clone());
+                    self.active_chunk = Some(Box::new(second.clone()));
                 } else {
-                    self.active_chunk = Some(first.clone());
+                    self.active_chunk = Some(Box::new(first.clone()));
                     if end > first_len {
-                        self.to_visit.push((second.clone(), Some(end - first_len)));
+                        self.to_visit
+                            .push((Box::new(second.clone()), Some(end - first_len)));
                     }
                 }
             }
@@ -88,57 +88,58 @@ impl Iterator for DynamicStringIterator {
 
     #[inline]
     fn next(&mut self) -> Option<u16> {
-        let part = match &self.active_chunk {
-            None => return None,
-            Some(s) => s.clone(),
-        };
+        // Moving to the next chunk loops instead of calling `next` again, so the depth of the
+        // tree does not limit the stack.
+        loop {
+            let part = match &self.active_chunk {
+                None => return None,
+                Some(s) => s.clone(),
+            };
 
-        let part = part.as_ref();
+            let part = part.as_ref();
 
-        match part {
-            DynamicString::Empty => {
-                self.advance_chunk();
-                self.next()
-            }
-            DynamicString::SlicedString {
-                root,
-                start,
-                length,
-            } => {
-                self.advance_slice_chunk(root, *start, *length);
-                self.next()
-            }
-            DynamicString::ConsString { first, second } => {
-                self.advance_cons_chunk(first, second);
-                self.next()
-            }
-            DynamicString::SingleOneByteChar(b) => {
-                self.advance_chunk();
-                Some(*b as u16)
-            }
-            DynamicString::SingleTwoByteChar(b) => {
-                self.advance_chunk();
-                Some(*b as u16)
-            }
-            DynamicString::SeqOneByteString(vec) => {
-                if self.chunk_index == self.actual_len(vec.len()) {
+            match part {
+                DynamicString::Empty => {
                     self.advance_chunk();
-                    return self.next();
                 }
-
-                let byte = vec[self.chunk_index];
-                self.chunk_index += 1;
-                Some(byte as u16)
-            }
-            DynamicString::SeqTwoByteString(vec) => {
-                if self.chunk_index == self.actual_len(vec.len()) {
+                DynamicString::SlicedString {
+                    root,
+                    start,
+                    length,
+                } => {
+                    self.advance_slice_chunk(root, *start, *length);
+                }
+                DynamicString::ConsString(node) => {
+                    self.advance_cons_chunk(node.first(), node.second());
+                }
+                DynamicString::SingleOneByteChar(b) => {
+                    self.advance_chunk();
+                    return Some(*b as u16);
+                }
+                DynamicString::SingleTwoByteChar(b) => {
                     self.advance_chunk();
-                    return self.next();
+                    return Some(*b);
                 }
+                DynamicString::SeqOneByteString(vec) => {
+                    if self.chunk_index == self.actual_len(vec.len()) {
+                        self.advance_chunk();
+                        continue;
+                    }
 
-                let byte = vec[self.chunk_index];
-                self.chunk_index += 1;
-                Some(byte)
+                    let byte = vec[self.chunk_index];
+                    self.chunk_index += 1;
+                    return Some(byte as u16);
+                }
+                DynamicString::SeqTwoByteString(vec) => {
+                    if self.chunk_index == self.actual_len(vec.len()) {
+                        self.advance_chunk();
+                        continue;
+                    }
+
+                    let byte = vec[self.chunk_index];
+                    self.chunk_index += 1;
+                    return Some(byte);
+                }
             }
         }
     }
@@ -172,8 +173,8 @@ impl Iterator for DynamicStringIterator {
                     self.advance_slice_chunk(root, *start, *length);
                     continue;
                 }
-                DynamicString::ConsString { first, second } => {
-                    self.advance_cons_chunk(first, second);
+                DynamicString::ConsString(node) => {
+                    self.advance_cons_chunk(node.first(), node.second());
                     continue;
                 }
                 DynamicString::SingleOneByteChar(_) | DynamicString::SingleTwoByteChar(_) => {
@@ -217,3 +218,78 @@ impl IntoIterator for DynamicString {
         }
     }
 }
+
+/// Iterator over the contiguous pieces of memory of a DynamicString, returned by
+/// `DynamicString::chunks` and `DynamicString::rchunks`. Unlike `DynamicStringIterator` it
+/// borrows the string and does not clone any of its nodes.
+#[derive(Debug, Clone)]
+pub struct DynamicStringChunks<'a> {
+    /// Nodes that are yet to be visited with the range of each one that is included, the
+    /// next node is on the top.
+    to_visit: Vec<(&'a DynamicString, usize, usize)>,
+    /// Visit the chunks from the end of the string to its start.
+    reverse: bool,
+}
+
+impl<'a> DynamicStringChunks<'a> {
+    pub(crate) fn new(string: &'a DynamicString, reverse: bool) -> Self {
+        let mut to_visit = Vec::with_capacity(8);
+        to_visit.push((string, 0, string.len()));
+        DynamicStringChunks { to_visit, reverse }
+    }
+}
+
+impl<'a> Iterator for DynamicStringChunks<'a> {
+    type Item = ChunkSlice<'a>;
+
+    fn next(&mut self) -> Option<ChunkSlice<'a>> {
+        while let Some((node, start, end)) = self.to_visit.pop() {
+            if start >= end {
+                continue;
+            }
+
+            match node {
+                DynamicString::Empty => {}
+                DynamicString::SingleOneByteChar(c) => {
+                    return Some(ChunkSlice::OneByte(std::slice::from_ref(c)));
+                }
+                DynamicString::SingleTwoByteChar(c) => {
+                    return Some(ChunkSlice::TwoByte(std::slice::from_ref(c)));
+                }
+                DynamicString::SeqOneByteString(vec) => {
+                    return Some(ChunkSlice::OneByte(&vec[start..end]));
+                }
+                DynamicString::SeqTwoByteString(vec) => {
+                    return Some(ChunkSlice::TwoByte(&vec[start..end]));
+                }
+                DynamicString::SlicedString {
+                    root,
+                    start: offset,
+                    ..
+                } => {
+                    self.to_visit.push((root, offset + start, offset + end));
+                }
+                DynamicString::ConsString(cons) => {
+                    let (first, second) = (cons.first(), cons.second());
+                    let first_len = first.len();
+                    let first = (first, start, cmp::min(end, first_len));
+                    let second = (
+                        second,
+                        start.saturating_sub(first_len),
+                        end.saturating_sub(first_len),
+                    );
+                    // Empty ranges are skipped when they are popped.
+                    if self.reverse {
+                        self.to_visit.push(first);
+                        self.to_visit.push(second);
+                    } else {
+                        self.to_visit.push(second);
+                        self.to_visit.push(first);
+                    }
+                }
+            }
+        }
+
+        None
+    }
+}
@@ -25,16 +25,35 @@ Note: Any string that has less than 16 bytes is flattened.
 (Gets copied instead of being referenced.)
 */
 
+mod case;
+mod case_folding;
+mod code_point;
+mod cons;
+mod encoding;
+mod error;
 mod indexed;
 mod iterator;
+mod line;
 mod methods;
+mod multi_pattern;
+mod offsets;
 mod pattern;
+mod regex;
 mod string;
+mod summary;
+mod utf8;
 
+pub use case::*;
+pub use code_point::*;
+pub use error::*;
 pub use indexed::*;
 pub use iterator::*;
-pub use methods::*;
+pub use line::*;
+pub use multi_pattern::*;
 pub use pattern::*;
+pub use regex::*;
 pub use string::*;
+pub use summary::*;
+pub use utf8::*;
 
 pub(crate) const MIN_SLICE_LENGTH: usize = 16;
@@ -0,0 +1,102 @@
+use super::DynamicString;
+
+/// The LineIndex maps offsets of a DynamicString to line and column numbers and back, lines
+/// are separated like in JavaScript by `\n`, `\r\n`, `\r`, U+2028 and U+2029.
+///
+/// Lines and columns are zero-based and columns are counted in UTF-16 code units.
+/// ```
+/// use dynstr::{DynamicString, LineIndex};
+/// let index = LineIndex::new(DynamicString::new("let a;\r\nlet b;\nlet c;"));
+/// assert_eq!(index.line_count(), 3);
+/// assert_eq!(index.line_col(12), Some((1, 4)));
+/// assert_eq!(index.offset(2, 4), Some(19));
+/// assert_eq!(index.line(1).unwrap(), "let b;");
+/// ```
+#[derive(Debug, Clone)]
+pub struct LineIndex {
+    string: DynamicString,
+    /// Offset of the first character of each line.
+    starts: Vec<usize>,
+}
+
+impl LineIndex {
+    /// Creates a new LineIndex from a DynamicString.
+    pub fn new(string: DynamicString) -> Self {
+        let mut starts = vec![0];
+        let mut prev = 0;
+        for (i, c) in string.iter().enumerate() {
+            match c {
+                // The line break of a `\r\n` has already been recorded.
+                LF if prev == CR => *starts.last_mut().unwrap() = i + 1,
+                LF | CR | LINE_SEPARATOR | PARAGRAPH_SEPARATOR => starts.push(i + 1),
+                _ => {}
+            }
+            prev = c;
+        }
+
+        LineIndex { string, starts }
+    }
+
+    /// Returns the number of lines, an empty string has one line.
+    #[inline]
+    pub fn line_count(&self) -> usize {
+        self.starts.len()
+    }
+
+    /// Returns the line and column of the given offset, or `None` if the offset is past the
+    /// end of the string.
+    pub fn line_col(&self, offset: usize) -> Option<(usize, usize)> {
+        if offset > self.string.len() {
+            return None;
+        }
+
+        let line = self.starts.partition_point(|&start| start <= offset) - 1;
+        Some((line, offset - self.starts[line]))
+    }
+
+    /// Returns the offset of the given line and column, or `None` if the line does not exist
+    /// or the column is past the end of the line.
+    pub fn offset(&self, line: usize, col: usize) -> Option<usize> {
+        let start = *self.starts.get(line)?;
+        if col > self.line_end(line) - start {
+            None
+        } else {
+            Some(start + col)
+        }
+    }
+
+    /// Returns the content of the given line without its line terminator.
+    pub fn line(&self, line: usize) -> Option<DynamicString> {
+        let start = *self.starts.get(line)?;
+        Some(self.string.slice(start, self.line_end(line) - start))
+    }
+
+    /// Returns the offset right after the last character of the line, before the terminator.
+    fn line_end(&self, line: usize) -> usize {
+        let next = match self.starts.get(line + 1) {
+            Some(&next) => next,
+            None => return self.string.len(),
+        };
+
+        // A `\r\n` terminator is two characters long.
+        let crlf = next >= self.starts[line] + 2
+            && self.string.char_code_at(next - 2) == Some(CR)
+            && self.string.char_code_at(next - 1) == Some(LF);
+        if crlf {
+            next - 2
+        } else {
+            next - 1
+        }
+    }
+}
+
+impl From<DynamicString> for LineIndex {
+    fn from(string: DynamicString) -> Self {
+        LineIndex::new(string)
+    }
+}
+
+pub(crate) const LF: u16 = 0x0a;
+pub(crate) const CR: u16 = 0x0d;
+pub(crate) const LINE_SEPARATOR: u16 = 0x2028;
+pub(crate) const PARAGRAPH_SEPARATOR: u16 = 0x2029;
@@ -1,5 +1,11 @@
-use super::{DynamicString, PatternFinder, MIN_SLICE_LENGTH};
+use super::encoding::{is_lead_surrogate, is_trail_surrogate};
+use super::pattern::is_whitespace;
+use super::{
+    DynamicString, DynamicStringError, MatchIndices, MatchMode, Matcher, Matches, Pattern, Split,
+    MIN_SLICE_LENGTH,
+};
 use std::cmp;
+use std::ops::{Bound, RangeBounds};
 
 impl DynamicString {
     /// Extracts a section of a string and returns it as a new string, without modifying
@@ -36,6 +42,235 @@ impl DynamicString {
         }
     }
 
+    /// Extracts the section of the string in the given range, out of bound indices are clamped
+    /// to the length of the string and an empty string is returned when the start of the range
+    /// is after its end.
+    /// ```
+    /// use dynstr::DynamicString;
+    /// let str = DynamicString::new("Hello world");
+    /// assert_eq!(str.substring(6..), "world");
+    /// assert_eq!(str.substring(..5), "Hello");
+    /// assert_eq!(str.substring(..=4), "Hello");
+    /// assert_eq!(str.substring(4..7), "o w");
+    /// assert_eq!(str.substring(6..100), "world");
+    /// ```
+    pub fn substring<R: RangeBounds<usize>>(&self, range: R) -> Self {
+        let (start, end) = clamp_range(&range, self.len());
+        self.slice(start, end - start)
+    }
+
+    /// Divides the string into two at the given index, the index is clamped to the length of
+    /// the string.
+    /// ```
+    /// use dynstr::DynamicString;
+    /// let (first, second) = DynamicString::new("Hello world").split_at(5);
+    /// assert_eq!(first, "Hello");
+    /// assert_eq!(second, " world");
+    /// ```
+    pub fn split_at(&self, index: usize) -> (Self, Self) {
+        let len = self.len();
+        let index = cmp::min(index, len);
+        (self.slice(0, index), self.slice(index, len - index))
+    }
+
+    /// Like `slice` but returns an error instead of clamping the range when it goes past the
+    /// end of the string.
+    /// ```
+    /// use dynstr::{DynamicString, DynamicStringError};
+    /// let str = DynamicString::new("Hello");
+    /// assert_eq!(str.try_slice(1, 3).unwrap(), "ell");
+    /// assert_eq!(str.try_slice(5, 0).unwrap(), "");
+    /// assert_eq!(
+    ///     str.try_slice(3, 5),
+    ///     Err(DynamicStringError::OutOfBounds { index: 8, len: 5 })
+    /// );
+    /// ```
+    pub fn try_slice(&self, start: usize, length: usize) -> Result<Self, DynamicStringError> {
+        let len = self.len();
+        match start.checked_add(length) {
+            Some(end) if end <= len => Ok(self.slice(start, length)),
+            end => Err(DynamicStringError::OutOfBounds {
+                index: end.unwrap_or(usize::MAX),
+                len,
+            }),
+        }
+    }
+
+    /// Returns the sub-string in the given range of UTF-16 code units, returns an error if the
+    /// range is out of bounds or if any of its ends is in the middle of a surrogate pair.
+    /// ```
+    /// use dynstr::{DynamicString, DynamicStringError};
+    /// let str = DynamicString::new("a😴b");
+    /// assert_eq!(str.get(1..3).unwrap(), "😴");
+    /// assert_eq!(str.get(..2), Err(DynamicStringError::SplitsSurrogate { index: 2 }));
+    /// assert_eq!(str.get(3..=4), Err(DynamicStringError::OutOfBounds { index: 5, len: 4 }));
+    /// ```
+    pub fn get<R: RangeBounds<usize>>(&self, range: R) -> Result<Self, DynamicStringError> {
+        let (start, end) = resolve_range(&range, self.len())?;
+        for &index in &[start, end] {
+            if !self.is_char_boundary(index) {
+                return Err(DynamicStringError::SplitsSurrogate { index });
+            }
+        }
+        Ok(self.slice(start, end - start))
+    }
+
+    /// Like `slice` but moves the bounds of the range inwards so that it never splits a
+    /// surrogate pair, the result may be shorter than the requested length.
+    /// ```
+    /// use dynstr::DynamicString;
+    /// let str = DynamicString::new("a😴b");
+    /// assert_eq!(str.slice_safe(0, 2), "a");
+    /// assert_eq!(str.slice_safe(2, 2), "b");
+    /// assert_eq!(str.slice_safe(0, 3), "a😴");
+    /// ```
+    pub fn slice_safe(&self, start: usize, length: usize) -> Self {
+        let end = self.floor_char_boundary(start.saturating_add(length));
+        let start = self.ceil_char_boundary(start);
+        if end <= start {
+            return DynamicString::empty();
+        }
+        self.slice(start, end - start)
+    }
+
+    /// Extracts a section of the string where `start` and `length` are counted in code points
+    /// instead of UTF-16 code units, unpaired surrogates are counted as one code point.
+    /// ```
+    /// use dynstr::DynamicString;
+    /// let str = DynamicString::new("😴😄😃");
+    /// assert_eq!(str.slice_chars(1, 1), "😄");
+    /// assert_eq!(str.slice_chars(1, 5), "😄😃");
+    /// ```
+    pub fn slice_chars(&self, start: usize, length: usize) -> Self {
+        let mut start_index = None;
+        let mut end_index = self.len();
+        let mut index = 0;
+        for (n, c) in std::char::decode_utf16(self.iter()).enumerate() {
+            if n == start {
+                start_index = Some(index);
+            }
+            if n == start.saturating_add(length) {
+                end_index = index;
+                break;
+            }
+            index += c.map_or(1, char::len_utf16);
+        }
+
+        match start_index {
+            None => DynamicString::empty(),
+            Some(start) => self.slice(start, end_index - start),
+        }
+    }
+
+    /// Returns true if the given index is the start or the end of a code point, that is the
+    /// index is not in the middle of a surrogate pair. The start and the end of the string
+    /// are considered boundaries.
+    pub fn is_char_boundary(&self, index: usize) -> bool {
+        let len = self.len();
+        if index == 0 || index == len {
+            return true;
+        }
+        if index > len {
+            return false;
+        }
+
+        match (self.char_code_at(index - 1), self.char_code_at(index)) {
+            (Some(lead), Some(trail)) => !(is_lead_surrogate(lead) && is_trail_surrogate(trail)),
+            _ => true,
+        }
+    }
+
+    /// Finds the closest char boundary which is not greater than `index`, indices past the end
+    /// of the string are clamped to its length.
+    pub fn floor_char_boundary(&self, index: usize) -> usize {
+        if index >= self.len() {
+            self.len()
+        } else if self.is_char_boundary(index) {
+            index
+        } else {
+            index - 1
+        }
+    }
+
+    /// Finds the closest char boundary which is not less than `index`, indices past the end
+    /// of the string are clamped to its length.
+    pub fn ceil_char_boundary(&self, index: usize) -> usize {
+        if index >= self.len() {
+            self.len()
+        } else if self.is_char_boundary(index) {
+            index
+        } else {
+            index + 1
+        }
+    }
+
+    /// Returns the UTF-16 code unit at the given index, or `None` if the index is out of
+    /// bounds. This method descends the tree of the string and does not visit the nodes that
+    /// do not contain the index, for frequent random accesses use an `IndexedString`.
+    /// ```
+    /// use dynstr::DynamicString;
+    /// let str = DynamicString::new("Hello") + " world";
+    /// assert_eq!(str.char_code_at(6), Some('w' as u16));
+    /// assert_eq!(str.char_code_at(11), None);
+    /// ```
+    pub fn char_code_at(&self, mut index: usize) -> Option<u16> {
+        let mut current = self;
+        loop {
+            match current {
+                DynamicString::Empty => return None,
+                DynamicString::SingleOneByteChar(c) => {
+                    return if index == 0 { Some(*c as u16) } else { None }
+                }
+                DynamicString::SingleTwoByteChar(c) => {
+                    return if index == 0 { Some(*c) } else { None }
+                }
+                DynamicString::SeqOneByteString(v) => return v.get(index).map(|&c| c as u16),
+                DynamicString::SeqTwoByteString(v) => return v.get(index).copied(),
+                DynamicString::SlicedString {
+                    root,
+                    start,
+                    length,
+                } => {
+                    if index >= *length {
+                        return None;
+                    }
+                    index += start;
+                    current = root;
+                }
+                DynamicString::ConsString(node) => {
+                    let (first, second) = (node.first(), node.second());
+                    let first_len = first.len();
+                    if index < first_len {
+                        current = first;
+                    } else {
+                        index -= first_len;
+                        current = second;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Returns the UTF-16 code unit at the given index, like JavaScript's `String.at` negative
+    /// indices are counted back from the end of the string.
+    /// ```
+    /// use dynstr::DynamicString;
+    /// let str = DynamicString::new("Hello");
+    /// assert_eq!(str.at(0), Some('H' as u16));
+    /// assert_eq!(str.at(-1), Some('o' as u16));
+    /// assert_eq!(str.at(-6), None);
+    /// ```
+    pub fn at(&self, index: isize) -> Option<u16> {
+        if index >= 0 {
+            self.char_code_at(index as usize)
+        } else {
+            let back = index.unsigned_abs();
+            self.len()
+                .checked_sub(back)
+                .and_then(|index| self.char_code_at(index))
+        }
+    }
+
     /// Concatenate the current string with another string, returns the result.
     /// ```
     /// use dynstr::DynamicString;
@@ -49,10 +284,7 @@ impl DynamicString {
             _ => {}
         }
 
-        let ret = DynamicString::ConsString {
-            first: Box::new(self.clone()),
-            second: Box::new(other.clone()),
-        };
+        let ret = DynamicString::cons(self.clone(), other);
 
         if ret.len() < MIN_SLICE_LENGTH {
             ret.flatten()
@@ -68,8 +300,98 @@ impl DynamicString {
     /// assert_eq!(str.index_of("world"), Some(6));
     /// assert_eq!(str.index_of("world!"), None);
     /// ```
-    pub fn index_of<T: Into<DynamicString>>(&self, pattern: T) -> Option<usize> {
-        PatternFinder::new(self.clone(), pattern.into()).next()
+    #[inline]
+    pub fn index_of<P: Pattern>(&self, pattern: P) -> Option<usize> {
+        self.index_of_from(pattern, 0)
+    }
+
+    /// Return the index of the first occurrence of the specified value at or after `position`,
+    /// the position is clamped to the length of the string like in JavaScript.
+    /// ```
+    /// use dynstr::DynamicString;
+    /// let str = DynamicString::new("Hello world, hello world");
+    /// assert_eq!(str.index_of_from("world", 7), Some(19));
+    /// assert_eq!(str.index_of_from("", 100), Some(24));
+    /// ```
+    pub fn index_of_from<P: Pattern>(&self, pattern: P, position: usize) -> Option<usize> {
+        let position = cmp::min(position, self.len());
+        let text = self.substring(position..);
+        let mut matcher = pattern.into_matcher();
+        let first = matcher.find_iter(&text, MatchMode::Overlapping).next();
+        first.map(|(start, _)| position + start)
+    }
+
+    /// Return the index of the last occurrence of the specified value that starts at or before
+    /// `position`, or before the end of the string if the position is `None`.
+    /// ```
+    /// use dynstr::DynamicString;
+    /// let str = DynamicString::new("canal");
+    /// assert_eq!(str.last_index_of("a", None), Some(3));
+    /// assert_eq!(str.last_index_of("a", Some(2)), Some(1));
+    /// assert_eq!(str.last_index_of("a", Some(0)), None);
+    /// assert_eq!(str.last_index_of("", Some(2)), Some(2));
+    /// ```
+    pub fn last_index_of<P: Pattern>(&self, pattern: P, position: Option<usize>) -> Option<usize> {
+        let len = self.len();
+        let position = cmp::min(position.unwrap_or(len), len);
+        let mut matcher = pattern.into_matcher();
+        // Only the matches that start at or before the position are searched.
+        let text = self.substring(..position.saturating_add(matcher.max_len()));
+        let last = matcher
+            .rfind_iter(&text, MatchMode::Overlapping)
+            .find(|&(start, _)| start <= position);
+        last.map(|(start, _)| start)
+    }
+
+    /// Determines whether the specified value may be found within the string.
+    /// ```
+    /// use dynstr::DynamicString;
+    /// let str = DynamicString::new("To be, or not to be");
+    /// assert!(str.includes("not"));
+    /// assert!(!str.includes("question"));
+    /// ```
+    #[inline]
+    pub fn includes<P: Pattern>(&self, pattern: P) -> bool {
+        self.index_of(pattern).is_some()
+    }
+
+    /// Returns the number of non-overlapping occurrences of the pattern in the string.
+    /// ```
+    /// use dynstr::DynamicString;
+    /// let str = DynamicString::new("aaaa, a");
+    /// assert_eq!(str.count("aa"), 2);
+    /// assert_eq!(str.count('a'), 5);
+    /// assert_eq!(str.count(""), 8);
+    /// ```
+    #[inline]
+    pub fn count<P: Pattern>(&self, pattern: P) -> usize {
+        let mut matcher = pattern.into_matcher();
+        matcher.find_iter(self, MatchMode::NonOverlapping).count()
+    }
+
+    /// Returns an iterator over the non-overlapping matches of the pattern in the string.
+    /// ```
+    /// use dynstr::DynamicString;
+    /// let str = DynamicString::new("a1b22c333");
+    /// let digits: Vec<DynamicString> = str.matches(|c: u16| c < 0x3a).collect();
+    /// assert_eq!(digits, vec!["1", "2", "2", "3", "3", "3"]);
+    /// ```
+    #[inline]
+    pub fn matches<'a, P: Pattern + 'a>(&'a self, pattern: P) -> Matches<'a> {
+        Matches::new(self, pattern.into_matcher())
+    }
+
+    /// Returns an iterator over the non-overlapping matches of the pattern in the string, and
+    /// the index at which they start.
+    /// ```
+    /// use dynstr::DynamicString;
+    /// let str = DynamicString::new("abcXXXabcYYYabc");
+    /// let found: Vec<(usize, DynamicString)> = str.match_indices("abc").collect();
+    /// assert_eq!(found.iter().map(|(i, _)| *i).collect::<Vec<_>>(), vec![0, 6, 12]);
+    /// ```
+    #[inline]
+    pub fn match_indices<'a, P: Pattern + 'a>(&'a self, pattern: P) -> MatchIndices<'a> {
+        MatchIndices::new(self, pattern.into_matcher())
     }
 
     /// Divides a String into an ordered list of substrings, puts these substrings into a vector,
@@ -86,48 +408,314 @@ impl DynamicString {
     /// assert_eq!(DynamicString::new("ABC").split("", None), vec!["A", "B", "C"]);
     /// assert_eq!(DynamicString::new("").split("ABC", None), vec![""]);
     /// ```
-    pub fn split<T: Into<DynamicString>>(
-        &self,
-        separator: T,
-        limit: Option<usize>,
-    ) -> Vec<DynamicString> {
-        if limit == Some(0) {
+    pub fn split<P: Pattern>(&self, separator: P, limit: Option<usize>) -> Vec<DynamicString> {
+        let split = self.split_iter(separator);
+        match limit {
+            Some(n) => split.take(n).collect(),
+            None => split.collect(),
+        }
+    }
+
+    /// Returns a lazy iterator over the substrings of the string that are separated by the
+    /// pattern, with the same edge cases as `split`.
+    /// ```
+    /// use dynstr::DynamicString;
+    /// let str = DynamicString::new("a,b,c");
+    /// let first: Vec<DynamicString> = str.split_iter(",").take(2).collect();
+    /// assert_eq!(first, vec!["a", "b"]);
+    /// assert_eq!(DynamicString::new("ABC").split_iter("").count(), 3);
+    /// ```
+    #[inline]
+    pub fn split_iter<'a, P: Pattern + 'a>(&'a self, separator: P) -> Split<'a> {
+        Split::new(self, separator.into_matcher(), false)
+    }
+
+    /// Divides a String like `split`, but the pattern is searched from the end of the string
+    /// and the substrings are returned in reverse order. The limit applies to the last
+    /// substrings.
+    /// ```
+    /// use dynstr::DynamicString;
+    /// let str = DynamicString::new("Jack,Joe,John");
+    /// assert_eq!(str.rsplit(",", None), vec!["John", "Joe", "Jack"]);
+    /// assert_eq!(str.rsplit(",", Some(1)), vec!["John"]);
+    /// assert_eq!(DynamicString::new("aaa").rsplit("aa", None), vec!["", "a"]);
+    /// ```
+    pub fn rsplit<P: Pattern>(&self, separator: P, limit: Option<usize>) -> Vec<DynamicString> {
+        let split = self.rsplit_iter(separator);
+        match limit {
+            Some(n) => split.take(n).collect(),
+            None => split.collect(),
+        }
+    }
+
+    /// Returns a lazy iterator over the substrings of the string that are separated by the
+    /// pattern, starting from the end of the string, with the same edge cases as `rsplit`.
+    /// ```
+    /// use dynstr::DynamicString;
+    /// let path = DynamicString::new("/usr/local/bin/env");
+    /// assert_eq!(path.rsplit_iter("/").nth(1).unwrap(), "bin");
+    /// ```
+    #[inline]
+    pub fn rsplit_iter<'a, P: Pattern + 'a>(&'a self, separator: P) -> Split<'a> {
+        Split::new(self, separator.into_matcher(), true)
+    }
+
+    /// Divides the string like `split`, but returns at most `n` substrings, the last one
+    /// contains the rest of the string.
+    /// ```
+    /// use dynstr::DynamicString;
+    /// let str = DynamicString::new("key=value=more");
+    /// assert_eq!(str.splitn(2, "="), vec!["key", "value=more"]);
+    /// assert_eq!(str.splitn(1, "="), vec!["key=value=more"]);
+    /// assert_eq!(DynamicString::new("a,").splitn(5, ","), vec!["a", ""]);
+    /// assert!(str.splitn(0, "=").is_empty());
+    /// ```
+    pub fn splitn<P: Pattern>(&self, n: usize, separator: P) -> Vec<DynamicString> {
+        if n == 0 {
             return Vec::with_capacity(0);
         }
 
-        let separator = separator.into();
-        let sep_len = separator.len();
-        let patterns = PatternFinder::new(self.clone(), separator);
+        let mut matcher = separator.into_matcher();
+        let empty = matcher.max_len() == 0;
         let mut result = Vec::new();
         let mut last_index = 0;
+        let mut limited = false;
 
-        for index in patterns {
-            if !(sep_len == 0 && last_index == 0 && index == 0) {
-                result.push(self.slice(last_index, index - last_index));
+        for (start, end) in matcher.find_iter(self, MatchMode::NonOverlapping) {
+            if result.len() + 1 == n {
+                limited = true;
+                break;
             }
-            last_index = index + sep_len;
-            match limit {
-                Some(n) if n == result.len() => return result,
-                _ => {}
+            if !(empty && last_index == 0 && start == 0) {
+                result.push(self.slice(last_index, start - last_index));
             }
+            last_index = end;
         }
 
-        if last_index < self.len() {
+        // The rest of the string is the last substring when the limit is reached.
+        if limited || !empty || last_index < self.len() {
             result.push(self.slice(last_index, self.len() - last_index));
         }
 
         result
     }
 
+    /// Divides the string like `split`, but an empty substring after the last separator is
+    /// not included, so the separator works as a terminator.
+    /// ```
+    /// use dynstr::DynamicString;
+    /// let str = DynamicString::new("a;b;c;");
+    /// assert_eq!(str.split_terminator(";"), vec!["a", "b", "c"]);
+    /// assert_eq!(DynamicString::new("a;;").split_terminator(";"), vec!["a", ""]);
+    /// assert!(DynamicString::new("").split_terminator(";").is_empty());
+    /// ```
+    pub fn split_terminator<P: Pattern>(&self, separator: P) -> Vec<DynamicString> {
+        let mut result = self.split(separator, None);
+        if matches!(result.last(), Some(last) if last.is_empty()) {
+            result.pop();
+        }
+        result
+    }
+
+    /// Splits the string on the first occurrence of the separator, and returns the parts
+    /// before and after it, or `None` if the separator is not found.
+    /// ```
+    /// use dynstr::DynamicString;
+    /// let str = DynamicString::new("a.b.c");
+    /// let (before, after) = str.split_once(".").unwrap();
+    /// assert_eq!(before, "a");
+    /// assert_eq!(after, "b.c");
+    /// assert!(str.split_once(",").is_none());
+    /// ```
+    pub fn split_once<P: Pattern>(&self, separator: P) -> Option<(DynamicString, DynamicString)> {
+        let mut matcher = separator.into_matcher();
+        let first = matcher.find_iter(self, MatchMode::Overlapping).next();
+        let (start, end) = first?;
+        Some((self.slice(0, start), self.slice(end, self.len() - end)))
+    }
+
+    /// Splits the string on the last occurrence of the separator, and returns the parts
+    /// before and after it, or `None` if the separator is not found.
+    /// ```
+    /// use dynstr::DynamicString;
+    /// let str = DynamicString::new("a.b.c");
+    /// let (before, after) = str.rsplit_once(".").unwrap();
+    /// assert_eq!(before, "a.b");
+    /// assert_eq!(after, "c");
+    /// assert!(str.rsplit_once(",").is_none());
+    /// ```
+    pub fn rsplit_once<P: Pattern>(&self, separator: P) -> Option<(DynamicString, DynamicString)> {
+        let mut matcher = separator.into_matcher();
+        let last = matcher.rfind_iter(self, MatchMode::Overlapping).next();
+        let (start, end) = last?;
+        Some((self.slice(0, start), self.slice(end, self.len() - end)))
+    }
+
+    /// Returns a new string where the first occurrence of `pattern` is replaced by
+    /// `replacement`, the replacement is inserted as-is (`$` patterns are not expanded).
+    /// ```
+    /// use dynstr::DynamicString;
+    /// let str = DynamicString::new("aaaa");
+    /// assert_eq!(str.replace("aa", "b"), "baa");
+    /// assert_eq!(str.replace("", "-"), "-aaaa");
+    /// ```
+    pub fn replace<P: Pattern, U: Into<DynamicString>>(&self, pattern: P, replacement: U) -> Self {
+        let mut matcher = pattern.into_matcher();
+        let first = matcher.find_iter(self, MatchMode::Overlapping).next();
+        match first {
+            Some((start, end)) => {
+                self.slice(0, start) + replacement + self.slice(end, self.len() - end)
+            }
+            None => self.clone(),
+        }
+    }
+
+    /// Returns a new string where all the non-overlapping occurrences of `pattern` are replaced
+    /// by `replacement`, the replacement is inserted as-is (`$` patterns are not expanded).
+    /// ```
+    /// use dynstr::DynamicString;
+    /// let str = DynamicString::new("aaa");
+    /// assert_eq!(str.replace_all("aa", "b"), "ba");
+    /// assert_eq!(str.replace_all("", "-"), "-a-a-a-");
+    /// ```
+    pub fn replace_all<P: Pattern, U: Into<DynamicString>>(
+        &self,
+        pattern: P,
+        replacement: U,
+    ) -> Self {
+        let mut matcher = pattern.into_matcher();
+        let replacement = replacement.into();
+        let mut result = DynamicString::empty();
+        let mut last_index = 0;
+
+        for (start, end) in matcher.find_iter(self, MatchMode::NonOverlapping) {
+            result = result + self.slice(last_index, start - last_index) + replacement.clone();
+            last_index = end;
+        }
+
+        result + self.slice(last_index, self.len() - last_index)
+    }
+
     /// Determines whether a string begins with the characters of a specified string, returning
     /// true or false as appropriate.
-    pub fn starts_with<T: Into<DynamicString>>(&self, other: T) -> bool {
-        let o: DynamicString = other.into();
-        if o.len() > self.len() {
-            false
-        } else {
-            self.iter().take(o.len()).eq(o.iter())
-        }
+    #[inline]
+    pub fn starts_with<P: Pattern>(&self, pattern: P) -> bool {
+        self.starts_with_at(pattern, 0)
+    }
+
+    /// Determines whether the characters of the specified string are found at `position`, the
+    /// position is clamped to the length of the string like in JavaScript.
+    /// ```
+    /// use dynstr::DynamicString;
+    /// let str = DynamicString::new("To be, or not to be");
+    /// assert!(str.starts_with_at("not", 10));
+    /// assert!(str.starts_with_at("", 100));
+    /// assert!(!str.starts_with_at("be", 100));
+    /// ```
+    pub fn starts_with_at<P: Pattern>(&self, pattern: P, position: usize) -> bool {
+        let position = cmp::min(position, self.len());
+        pattern.into_matcher().match_at(self, position).is_some()
+    }
+
+    /// Determines whether a string ends with the characters of a specified string, the string
+    /// is treated as if it were only `end_position` characters long when it is given.
+    /// ```
+    /// use dynstr::DynamicString;
+    /// let str = DynamicString::new("To be, or not to be");
+    /// assert!(str.ends_with("be", None));
+    /// assert!(str.ends_with("not", Some(13)));
+    /// assert!(!str.ends_with("To be", Some(4)));
+    /// ```
+    pub fn ends_with<P: Pattern>(&self, pattern: P, end_position: Option<usize>) -> bool {
+        let end = cmp::min(end_position.unwrap_or(usize::MAX), self.len());
+        pattern.into_matcher().match_ending_at(self, end).is_some()
+    }
+
+    /// Returns the string without the prefix, or `None` if the string does not start with it.
+    /// ```
+    /// use dynstr::DynamicString;
+    /// let str = DynamicString::new("foo:bar");
+    /// assert_eq!(str.strip_prefix("foo:").unwrap(), "bar");
+    /// assert!(str.strip_prefix("bar").is_none());
+    /// ```
+    pub fn strip_prefix<P: Pattern>(&self, prefix: P) -> Option<Self> {
+        let end = prefix.into_matcher().match_at(self, 0)?;
+        Some(self.substring(end..))
+    }
+
+    /// Returns the string without the suffix, or `None` if the string does not end with it.
+    /// ```
+    /// use dynstr::DynamicString;
+    /// let str = DynamicString::new("foo:bar");
+    /// assert_eq!(str.strip_suffix(":bar").unwrap(), "foo");
+    /// assert!(str.strip_suffix("foo").is_none());
+    /// ```
+    pub fn strip_suffix<P: Pattern>(&self, suffix: P) -> Option<Self> {
+        let start = suffix.into_matcher().match_ending_at(self, self.len())?;
+        Some(self.substring(..start))
+    }
+
+    /// Returns the string without the matches of the pattern at its start, repeatedly.
+    /// ```
+    /// use dynstr::DynamicString;
+    /// let str = DynamicString::new("xxyabcxy");
+    /// assert_eq!(str.trim_start_matches(&['x', 'y'][..]), "abcxy");
+    /// assert_eq!(str.trim_start_matches("xx"), "yabcxy");
+    /// ```
+    pub fn trim_start_matches<P: Pattern>(&self, pattern: P) -> Self {
+        let mut matcher = pattern.into_matcher();
+        let start = trimmed_start(&mut matcher, self, 0);
+        self.substring(start..)
+    }
+
+    /// Returns the string without the matches of the pattern at its end, repeatedly.
+    /// ```
+    /// use dynstr::DynamicString;
+    /// let str = DynamicString::new("xxyabcxy");
+    /// assert_eq!(str.trim_end_matches(&['x', 'y'][..]), "xxyabc");
+    /// ```
+    pub fn trim_end_matches<P: Pattern>(&self, pattern: P) -> Self {
+        let mut matcher = pattern.into_matcher();
+        let end = trimmed_end(&mut matcher, self, 0);
+        self.substring(..end)
+    }
+
+    /// Returns the string without the matches of the pattern at its start and its end,
+    /// repeatedly.
+    /// ```
+    /// use dynstr::DynamicString;
+    /// let str = DynamicString::new("123abc456");
+    /// assert_eq!(str.trim_matches(|c: u16| (0x30..0x3a).contains(&c)), "abc");
+    /// ```
+    pub fn trim_matches<P: Pattern>(&self, pattern: P) -> Self {
+        let mut matcher = pattern.into_matcher();
+        let start = trimmed_start(&mut matcher, self, 0);
+        let end = trimmed_end(&mut matcher, self, start);
+        self.substring(start..end)
+    }
+
+    /// Removes white space and line terminators from both ends of the string, like `trim` in
+    /// JavaScript.
+    /// ```
+    /// use dynstr::DynamicString;
+    /// let str = DynamicString::new("\u{feff} Hello world!\r\n");
+    /// assert_eq!(str.trim(), "Hello world!");
+    /// ```
+    #[inline]
+    pub fn trim(&self) -> Self {
+        self.trim_matches(is_whitespace)
+    }
+
+    /// Removes white space and line terminators from the start of the string.
+    #[inline]
+    pub fn trim_start(&self) -> Self {
+        self.trim_start_matches(is_whitespace)
+    }
+
+    /// Removes white space and line terminators from the end of the string.
+    #[inline]
+    pub fn trim_end(&self) -> Self {
+        self.trim_end_matches(is_whitespace)
     }
 }
 
@@ -138,3 +726,73 @@ impl<T: Into<DynamicString>> std::ops::Add<T> for DynamicString {
         self.append(rhs.into())
     }
 }
+
+/// Returns the offset after the matches of the pattern at the start of the string.
+fn trimmed_start(matcher: &mut Matcher, text: &DynamicString, mut start: usize) -> usize {
+    while let Some(end) = matcher.match_at(text, start) {
+        // An empty match would never move forward.
+        if end == start {
+            break;
+        }
+        start = end;
+    }
+    start
+}
+
+/// Returns the offset before the matches of the pattern at the end of the string, which is
+/// never smaller than `min`.
+fn trimmed_end(matcher: &mut Matcher, text: &DynamicString, min: usize) -> usize {
+    let mut end = text.len();
+    while let Some(start) = matcher.match_ending_at(text, end) {
+        if start == end || start < min {
+            break;
+        }
+        end = start;
+    }
+    end
+}
+
+/// Converts the range to a pair of `(start, end)` indices clamped to the length, the start is
+/// never greater than the end.
+pub(crate) fn clamp_range<R: RangeBounds<usize>>(range: &R, len: usize) -> (usize, usize) {
+    let start = match range.start_bound() {
+        Bound::Included(&n) => n,
+        Bound::Excluded(&n) => n.saturating_add(1),
+        Bound::Unbounded => 0,
+    };
+    let end = match range.end_bound() {
+        Bound::Included(&n) => n.saturating_add(1),
+        Bound::Excluded(&n) => n,
+        Bound::Unbounded => len,
+    };
+    let end = cmp::min(end, len);
+    (cmp::min(start, end), end)
+}
+
+/// Converts the range to a pair of `(start, end)` indices and checks them against the length.
+pub(crate) fn resolve_range<R: RangeBounds<usize>>(
+    range: &R,
+    len: usize,
+) -> Result<(usize, usize), DynamicStringError> {
+    let out_of_bounds = |index| DynamicStringError::OutOfBounds { index, len };
+    let start = match range.start_bound() {
+        Bound::Included(&n) => n,
+        Bound::Excluded(&n) => n.checked_add(1).ok_or_else(|| out_of_bounds(n))?,
+        Bound::Unbounded => 0,
+    };
+    let end = match range.end_bound() {
+        Bound::Included(&n) => n.checked_add(1).ok_or_else(|| out_of_bounds(n))?,
+        Bound::Excluded(&n) => n,
+        Bound::Unbounded => len,
+    };
+
+    if start > len {
+        Err(out_of_bounds(start))
+    } else if end > len {
+        Err(out_of_bounds(end))
+    } else if start > end {
+        Err(DynamicStringError::InvalidRange { start, end })
+    } else {
+        Ok((start, end))
+    }
+}
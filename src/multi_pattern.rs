@@ -0,0 +1,315 @@
+use super::pattern::units;
+use super::DynamicString;
+use std::borrow::Cow;
+use std::collections::VecDeque;
+
+/// Decides which match is reported when several patterns match at the same position, the
+/// leftmost match always wins and the search continues after its end.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MatchKind {
+    /// Prefer the pattern that comes first in the list of patterns.
+    LeftmostFirst,
+    /// Prefer the longest pattern, and the one that comes first in the list among the
+    /// patterns with the same length.
+    LeftmostLongest,
+}
+
+/// An Aho-Corasick automaton over UTF-16 code units, which finds the matches of many patterns
+/// in a single pass over the text. It can be reused to search many texts.
+///
+/// Empty patterns never match.
+/// ```
+/// use dynstr::{DynamicString, MatchKind, MultiPattern};
+/// let patterns = [DynamicString::new("Sam"), DynamicString::new("Samwise")];
+/// let text = DynamicString::new("Samwise and Sam");
+///
+/// let first = MultiPattern::new(&patterns, MatchKind::LeftmostFirst);
+/// let matches: Vec<(usize, usize)> = first.find_iter(&text).collect();
+/// assert_eq!(matches, vec![(0, 0), (0, 12)]);
+///
+/// let longest = MultiPattern::new(&patterns, MatchKind::LeftmostLongest);
+/// let matches: Vec<(usize, usize)> = longest.find_iter(&text).collect();
+/// assert_eq!(matches, vec![(1, 0), (0, 12)]);
+/// ```
+#[derive(Debug, Clone)]
+pub struct MultiPattern {
+    patterns: Vec<DynamicString>,
+    kind: MatchKind,
+    states: Vec<State>,
+}
+
+#[derive(Debug, Clone, Default)]
+struct State {
+    /// Transitions of the trie, sorted by the character.
+    next: Vec<(u16, usize)>,
+    /// The state of the longest proper suffix of this state that is in the trie.
+    fail: usize,
+    /// Length of the prefix that this state represents.
+    depth: usize,
+    /// Patterns that end at this state, including the ones of the failure states.
+    matches: Vec<usize>,
+}
+
+const ROOT: usize = 0;
+
+impl State {
+    #[inline]
+    fn get(&self, c: u16) -> Option<usize> {
+        self.next
+            .binary_search_by_key(&c, |&(c, _)| c)
+            .ok()
+            .map(|i| self.next[i].1)
+    }
+}
+
+impl MultiPattern {
+    /// Builds the automaton for the given patterns, the id of each pattern is its index in
+    /// the slice.
+    pub fn new(patterns: &[DynamicString], kind: MatchKind) -> Self {
+        let mut states = vec![State::default()];
+
+        for (id, pattern) in patterns.iter().enumerate() {
+            if pattern.is_empty() {
+                continue;
+            }
+
+            let mut current = ROOT;
+            for c in pattern.iter() {
+                current = match states[current].get(c) {
+                    Some(next) => next,
+                    None => {
+                        let next = states.len();
+                        states.push(State {
+                            depth: states[current].depth + 1,
+                            ..State::default()
+                        });
+                        let transitions = &mut states[current].next;
+                        let i = transitions.partition_point(|&(x, _)| x < c);
+                        transitions.insert(i, (c, next));
+                        next
+                    }
+                };
+            }
+            states[current].matches.push(id);
+        }
+
+        // Compute the failure transitions in breadth-first order, so the failure state of a
+        // state is always complete before the state itself.
+        let mut queue: VecDeque<usize> = states[ROOT].next.iter().map(|&(_, s)| s).collect();
+        while let Some(state) = queue.pop_front() {
+            for i in 0..states[state].next.len() {
+                let (c, next) = states[state].next[i];
+                let mut fail = states[state].fail;
+                let fail = loop {
+                    if let Some(target) = states[fail].get(c) {
+                        break target;
+                    }
+                    if fail == ROOT {
+                        break ROOT;
+                    }
+                    fail = states[fail].fail;
+                };
+                states[next].fail = fail;
+                let inherited = states[fail].matches.clone();
+                states[next].matches.extend(inherited);
+                queue.push_back(next);
+            }
+        }
+
+        MultiPattern {
+            patterns: patterns.to_vec(),
+            kind,
+            states,
+        }
+    }
+
+    /// Returns the patterns of this automaton.
+    #[inline]
+    pub fn patterns(&self) -> &[DynamicString] {
+        &self.patterns
+    }
+
+    /// Returns the match kind of this automaton.
+    #[inline]
+    pub fn kind(&self) -> MatchKind {
+        self.kind
+    }
+
+    /// Returns an iterator over the `(pattern_id, index)` of the matches in the text.
+    #[inline]
+    pub fn find_iter<'a>(&'a self, text: &'a DynamicString) -> MultiPatternFinder<'a> {
+        MultiPatternFinder::with_automaton(text, Cow::Borrowed(self))
+    }
+
+    /// Moves from the state by the character.
+    #[inline]
+    fn step(&self, mut state: usize, c: u16) -> usize {
+        loop {
+            if let Some(next) = self.states[state].get(c) {
+                return next;
+            }
+            if state == ROOT {
+                return ROOT;
+            }
+            state = self.states[state].fail;
+        }
+    }
+
+    /// Returns true if `a` should be reported instead of `b`, when both start at the same
+    /// position.
+    #[inline]
+    fn prefer(&self, a: &Match, b: &Match) -> bool {
+        match self.kind {
+            MatchKind::LeftmostFirst => a.id < b.id,
+            MatchKind::LeftmostLongest => a.end > b.end || (a.end == b.end && a.id < b.id),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Match {
+    id: usize,
+    start: usize,
+    end: usize,
+}
+
+/// A lazy iterator over the `(pattern_id, index)` of the non-overlapping matches of many
+/// patterns in a text, in ascending order of the index.
+/// ```
+/// use dynstr::{DynamicString, MatchKind, MultiPatternFinder};
+/// let patterns = [DynamicString::new("{{name}}"), DynamicString::new("{{day}}")];
+/// let text = DynamicString::new("Hello {{name}}, happy {{day}}!");
+/// let matches: Vec<(usize, usize)> =
+///     MultiPatternFinder::new(&text, &patterns, MatchKind::LeftmostFirst).collect();
+/// assert_eq!(matches, vec![(0, 6), (1, 22)]);
+/// ```
+pub struct MultiPatternFinder<'a> {
+    automaton: Cow<'a, MultiPattern>,
+    units: Box<dyn Iterator<Item = u16> + 'a>,
+    state: usize,
+    /// Number of characters that are consumed.
+    offset: usize,
+    /// Matches that are found but might still lose to a match that is not found yet.
+    candidates: Vec<Match>,
+    /// Matches that start before this offset overlap with the last reported match.
+    min_start: usize,
+    done: bool,
+}
+
+impl<'a> MultiPatternFinder<'a> {
+    /// Creates a finder that searches for the given patterns in the text, the id of each
+    /// pattern is its index in the slice.
+    pub fn new(text: &'a DynamicString, patterns: &[DynamicString], kind: MatchKind) -> Self {
+        Self::with_automaton(text, Cow::Owned(MultiPattern::new(patterns, kind)))
+    }
+
+    fn with_automaton(text: &'a DynamicString, automaton: Cow<'a, MultiPattern>) -> Self {
+        MultiPatternFinder {
+            automaton,
+            units: Box::new(units(text)),
+            state: ROOT,
+            offset: 0,
+            candidates: Vec::new(),
+            min_start: 0,
+            done: false,
+        }
+    }
+
+    /// Returns the best candidate if no match that is found later can replace it.
+    fn resolve(&mut self) -> Option<Match> {
+        let automaton = &self.automaton;
+        let min_start = self.min_start;
+        self.candidates.retain(|m| m.start >= min_start);
+
+        let mut best: Option<&Match> = None;
+        for m in &self.candidates {
+            best = match best {
+                Some(b) if m.start > b.start || (m.start == b.start && !automaton.prefer(m, b)) => {
+                    Some(b)
+                }
+                _ => Some(m),
+            };
+        }
+        let best = *best?;
+
+        // The matches that are found later start at or after this offset.
+        let next_start = self.offset - self.automaton.states[self.state].depth;
+        if self.done || best.start < next_start {
+            self.min_start = best.end;
+            Some(best)
+        } else {
+            None
+        }
+    }
+}
+
+impl<'a> Iterator for MultiPatternFinder<'a> {
+    type Item = (usize, usize);
+
+    fn next(&mut self) -> Option<(usize, usize)> {
+        loop {
+            if let Some(m) = self.resolve() {
+                return Some((m.id, m.start));
+            }
+            if self.done {
+                return None;
+            }
+
+            match self.units.next() {
+                Some(c) => {
+                    self.state = self.automaton.step(self.state, c);
+                    self.offset += 1;
+                    let offset = self.offset;
+                    let automaton = &self.automaton;
+                    self.candidates
+                        .extend(
+                            automaton.states[self.state]
+                                .matches
+                                .iter()
+                                .map(|&id| Match {
+                                    id,
+                                    start: offset - automaton.patterns[id].len(),
+                                    end: offset,
+                                }),
+                        );
+                }
+                None => self.done = true,
+            }
+        }
+    }
+}
+
+impl DynamicString {
+    /// Replaces the matches of the patterns of the automaton with the replacement that has the
+    /// same id, in a single pass.
+    /// ```
+    /// use dynstr::{DynamicString, MatchKind, MultiPattern};
+    /// let patterns = [DynamicString::new("{{name}}"), DynamicString::new("{{day}}")];
+    /// let automaton = MultiPattern::new(&patterns, MatchKind::LeftmostFirst);
+    /// let text = DynamicString::new("Hello {{name}}, happy {{day}}!");
+    /// let result = text.replace_all_multi(
+    ///     &automaton,
+    ///     &[DynamicString::new("Alice"), DynamicString::new("birthday")],
+    /// );
+    /// assert_eq!(result, "Hello Alice, happy birthday!");
+    /// ```
+    ///
+    /// # Panics
+    /// If the number of replacements is not the same as the number of patterns.
+    pub fn replace_all_multi(
+        &self,
+        patterns: &MultiPattern,
+        replacements: &[DynamicString],
+    ) -> DynamicString {
+        assert_eq!(patterns.patterns().len(), replacements.len());
+        let mut result = DynamicString::empty();
+        let mut last_index = 0;
+
+        for (id, start) in patterns.find_iter(self) {
+            result = result + self.slice(last_index, start - last_index) + replacements[id].clone();
+            last_index = start + patterns.patterns()[id].len();
+        }
+
+        result + self.slice(last_index, self.len() - last_index)
+    }
+}
@@ -0,0 +1,175 @@
+use super::encoding::{is_lead_surrogate, is_trail_surrogate};
+use super::IndexedString;
+
+/// Maximum number of characters in a block that is not simple, lookups scan at most this many
+/// characters after the binary search.
+const BLOCK_SIZE: usize = 64;
+
+/// Returns the weight of the character `c` in the target offset space, `prev` is the
+/// character before it.
+pub(crate) type Weight = fn(prev: Option<u16>, c: u16) -> usize;
+
+/// Maps UTF-16 offsets of an IndexedString to another offset space (such as code points or
+/// UTF-8 bytes) and back, where the size of each character in the target space is given by a
+/// weight function.
+///
+/// The string is divided into blocks that record their offsets in both spaces, so lookups
+/// are a binary search followed by a bounded scan.
+#[derive(Debug, Clone)]
+pub(crate) struct OffsetIndex {
+    blocks: Vec<Block>,
+    weight: Weight,
+    total: usize,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Block {
+    /// UTF-16 offset of the start of the block.
+    unit: usize,
+    /// Offset of the start of the block in the target space.
+    offset: usize,
+    /// In a simple block every character starts a code point and has a weight of one, so
+    /// offsets inside of it map one-to-one.
+    simple: bool,
+}
+
+impl OffsetIndex {
+    pub fn new(string: &IndexedString, weight: Weight) -> Self {
+        let mut blocks = Vec::<Block>::new();
+        let mut unit = 0;
+        let mut offset = 0;
+        let mut prev: Option<u16> = None;
+
+        for chunk in string.chunks_in(..) {
+            let mut start = 0;
+            while start < chunk.len() {
+                let end = std::cmp::min(start + BLOCK_SIZE, chunk.len());
+                let mut sum = 0;
+                let mut simple = true;
+                for i in start..end {
+                    let c = chunk.get(i).unwrap();
+                    let w = weight(prev, c);
+                    simple = simple && w == 1 && starts_code_point(prev, c);
+                    sum += w;
+                    prev = Some(c);
+                }
+
+                match blocks.last() {
+                    // Consecutive simple blocks are merged into one.
+                    Some(last) if last.simple && simple => {}
+                    _ => blocks.push(Block {
+                        unit,
+                        offset,
+                        simple,
+                    }),
+                }
+
+                unit += end - start;
+                offset += sum;
+                start = end;
+            }
+        }
+
+        if blocks.is_empty() {
+            blocks.push(Block {
+                unit: 0,
+                offset: 0,
+                simple: true,
+            });
+        }
+
+        OffsetIndex {
+            blocks,
+            weight,
+            total: offset,
+        }
+    }
+
+    /// Returns the length of the string in the target space.
+    #[inline]
+    pub fn total(&self) -> usize {
+        self.total
+    }
+
+    /// Converts a UTF-16 offset to the target space, an offset in the middle of a surrogate
+    /// pair is moved to the start of the pair.
+    pub fn to_offset(&self, string: &IndexedString, unit: usize) -> Option<usize> {
+        let len = string.len();
+        if unit > len {
+            return None;
+        }
+        if unit == len {
+            return Some(self.total);
+        }
+
+        let unit = floor_code_point(string, unit);
+        let block = self.blocks[self.blocks.partition_point(|b| b.unit <= unit) - 1];
+        if block.simple {
+            return Some(block.offset + unit - block.unit);
+        }
+
+        let mut prev = previous_unit(string, block.unit);
+        let mut offset = block.offset;
+        for c in string.iter_range(block.unit..unit) {
+            offset += (self.weight)(prev, c);
+            prev = Some(c);
+        }
+        Some(offset)
+    }
+
+    /// Converts an offset in the target space to a UTF-16 offset, an offset in the middle of
+    /// a code point is moved to the start of the code point.
+    pub fn to_unit(&self, string: &IndexedString, offset: usize) -> Option<usize> {
+        if offset > self.total {
+            return None;
+        }
+        if offset == self.total {
+            return Some(string.len());
+        }
+
+        let block = self.blocks[self.blocks.partition_point(|b| b.offset <= offset) - 1];
+        if block.simple {
+            return Some(block.unit + offset - block.offset);
+        }
+
+        // The block might start with the second half of a pair that started in the previous
+        // block, in which case the start of the pair is the first candidate.
+        let mut result = floor_code_point(string, block.unit);
+        let mut prev = previous_unit(string, block.unit);
+        let mut current = block.offset;
+        for (unit, c) in (block.unit..).zip(string.iter_range(block.unit..)) {
+            if starts_code_point(prev, c) {
+                if current > offset {
+                    break;
+                }
+                result = unit;
+            }
+            current += (self.weight)(prev, c);
+            prev = Some(c);
+        }
+        Some(result)
+    }
+}
+
+/// Returns false if `c` is the second half of a surrogate pair.
+#[inline(always)]
+pub(crate) fn starts_code_point(prev: Option<u16>, c: u16) -> bool {
+    match prev {
+        Some(p) => !(is_lead_surrogate(p) && is_trail_surrogate(c)),
+        None => true,
+    }
+}
+
+#[inline]
+fn previous_unit(string: &IndexedString, unit: usize) -> Option<u16> {
+    unit.checked_sub(1).and_then(|n| string.get(n))
+}
+
+/// Moves the offset to the start of the code point that contains it.
+#[inline]
+fn floor_code_point(string: &IndexedString, unit: usize) -> usize {
+    match string.get(unit) {
+        Some(c) if !starts_code_point(previous_unit(string, unit), c) => unit - 1,
+        _ => unit,
+    }
+}
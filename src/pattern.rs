@@ -1,39 +1,637 @@
-use super::{DynamicString, IndexedString};
+use super::case::{fold, fold_ascii};
+use super::encoding::{is_lead_surrogate, is_trail_surrogate};
+use super::{CaseSensitivity, ChunkSlice, DynamicString, DynamicStringChunks};
+use std::cmp;
+use std::collections::VecDeque;
+use std::convert::TryInto;
+use std::fmt;
+use std::ops::RangeInclusive;
+
+/// Patterns of at least this many characters are searched in one-byte texts using
+/// Boyer-Moore-Horspool. With three characters it is only faster than KMP on ropes of short
+/// chunks (`benches/search.rs`).
+const HORSPOOL_MIN_LENGTH: usize = 4;
+
+/// The same threshold for texts with two-byte characters, where KMP is slower because every
+/// character is read from a wider chunk while Horspool still skips most of them.
+const HORSPOOL_MIN_LENGTH_TWO_BYTE: usize = 3;
+
+/// Decides where the search continues after a match.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MatchMode {
+    /// Every occurrence is reported, even if it overlaps with the previous one.
+    Overlapping,
+    /// The search continues after the end of the previous match, like in `split` and
+    /// `replaceAll` in JavaScript.
+    NonOverlapping,
+}
+
+/// A pattern that can be searched for in a DynamicString.
+///
+/// It is implemented for strings (`&str` and `DynamicString`), single characters (`char` and
+/// `u16`), sets of characters (`&[char]` and `[char; N]`) and predicates over UTF-16 code
+/// units (`FnMut(u16) -> bool`).
+/// ```
+/// use dynstr::DynamicString;
+/// let str = DynamicString::new("a,b;c d");
+/// assert_eq!(str.split(&[',', ';'][..], None), vec!["a", "b", "c d"]);
+/// assert_eq!(str.split(|c: u16| c == ' ' as u16, None), vec!["a,b;c", "d"]);
+/// assert_eq!(str.index_of('c'), Some(4));
+/// ```
+pub trait Pattern: Sized {
+    /// Converts the pattern to the matcher that is used by the searching methods.
+    fn into_matcher<'p>(self) -> Matcher<'p>
+    where
+        Self: 'p;
+}
+
+/// The form of a Pattern that is used by the searching methods.
+pub struct Matcher<'p>(MatcherInner<'p>);
+
+enum MatcherInner<'p> {
+    /// Matches a sequence of characters.
+    Str(DynamicString),
+    /// Matches a single code unit for which the predicate returns true.
+    Unit(Box<dyn FnMut(u16) -> bool + 'p>),
+    /// Matches a code point that is in the set, a surrogate pair is matched as a whole.
+    CodePoint(Vec<char>),
+}
+
+impl Pattern for DynamicString {
+    #[inline]
+    fn into_matcher<'p>(self) -> Matcher<'p> {
+        Matcher(MatcherInner::Str(self))
+    }
+}
+
+impl Pattern for &DynamicString {
+    #[inline]
+    fn into_matcher<'p>(self) -> Matcher<'p>
+    where
+        Self: 'p,
+    {
+        Matcher(MatcherInner::Str(self.clone()))
+    }
+}
+
+impl Pattern for &str {
+    #[inline]
+    fn into_matcher<'p>(self) -> Matcher<'p>
+    where
+        Self: 'p,
+    {
+        Matcher(MatcherInner::Str(DynamicString::new(self)))
+    }
+}
+
+impl Pattern for char {
+    #[inline]
+    fn into_matcher<'p>(self) -> Matcher<'p> {
+        Matcher(MatcherInner::Str(DynamicString::new(
+            self.encode_utf8(&mut [0; 4]),
+        )))
+    }
+}
+
+impl Pattern for u16 {
+    #[inline]
+    fn into_matcher<'p>(self) -> Matcher<'p> {
+        Matcher(MatcherInner::Str(DynamicString::from_units(vec![self])))
+    }
+}
+
+impl Pattern for &[char] {
+    #[inline]
+    fn into_matcher<'p>(self) -> Matcher<'p>
+    where
+        Self: 'p,
+    {
+        Matcher(MatcherInner::CodePoint(self.to_vec()))
+    }
+}
+
+impl<const N: usize> Pattern for [char; N] {
+    #[inline]
+    fn into_matcher<'p>(self) -> Matcher<'p> {
+        Matcher(MatcherInner::CodePoint(self.to_vec()))
+    }
+}
+
+impl<const N: usize> Pattern for &[char; N] {
+    #[inline]
+    fn into_matcher<'p>(self) -> Matcher<'p>
+    where
+        Self: 'p,
+    {
+        Matcher(MatcherInner::CodePoint(self.to_vec()))
+    }
+}
+
+impl<F: FnMut(u16) -> bool> Pattern for F {
+    #[inline]
+    fn into_matcher<'p>(self) -> Matcher<'p>
+    where
+        Self: 'p,
+    {
+        Matcher(MatcherInner::Unit(Box::new(self)))
+    }
+}
+
+impl<'p> Matcher<'p> {
+    /// Returns the maximum number of characters in a match.
+    pub(crate) fn max_len(&self) -> usize {
+        match &self.0 {
+            MatcherInner::Str(s) => s.len(),
+            MatcherInner::Unit(_) => 1,
+            MatcherInner::CodePoint(_) => 2,
+        }
+    }
+
+    /// Returns a matcher that borrows the state of this matcher.
+    fn by_ref(&mut self) -> Matcher<'_> {
+        Matcher(match &mut self.0 {
+            MatcherInner::Str(s) => MatcherInner::Str(s.clone()),
+            MatcherInner::Unit(predicate) => MatcherInner::Unit(Box::new(predicate)),
+            MatcherInner::CodePoint(set) => MatcherInner::CodePoint(set.clone()),
+        })
+    }
+
+    /// Returns the `(start, end)` of the matches in the text, in ascending order.
+    #[inline]
+    pub(crate) fn find_iter<'a>(
+        &'a mut self,
+        text: &'a DynamicString,
+        mode: MatchMode,
+    ) -> Box<dyn Iterator<Item = (usize, usize)> + 'a> {
+        self.by_ref().into_find_iter(text, mode)
+    }
+
+    /// Returns the `(start, end)` of the matches in the text, in descending order.
+    #[inline]
+    pub(crate) fn rfind_iter<'a>(
+        &'a mut self,
+        text: &'a DynamicString,
+        mode: MatchMode,
+    ) -> Box<dyn Iterator<Item = (usize, usize)> + 'a> {
+        self.by_ref().into_rfind_iter(text, mode)
+    }
+
+    /// Like `find_iter`, but the matcher is moved into the iterator.
+    #[inline]
+    pub(crate) fn into_find_iter<'a>(
+        self,
+        text: &'a DynamicString,
+        mode: MatchMode,
+    ) -> Box<dyn Iterator<Item = (usize, usize)> + 'a>
+    where
+        'p: 'a,
+    {
+        self.into_find_iter_with_case(text, mode, CaseSensitivity::Sensitive)
+    }
+
+    /// Like `into_find_iter`, but the characters are compared using the given case
+    /// sensitivity. Predicates are called with the characters of the text as they are.
+    pub(crate) fn into_find_iter_with_case<'a>(
+        self,
+        text: &'a DynamicString,
+        mode: MatchMode,
+        case: CaseSensitivity,
+    ) -> Box<dyn Iterator<Item = (usize, usize)> + 'a>
+    where
+        'p: 'a,
+    {
+        match self.0 {
+            MatcherInner::Str(s) => {
+                let len = s.len();
+                Box::new(PatternFinder::with_case(text, &s, mode, case).map(move |i| (i, i + len)))
+            }
+            MatcherInner::Unit(mut predicate) => Box::new(
+                units(text)
+                    .enumerate()
+                    .filter(move |&(_, c)| predicate(c))
+                    .map(|(i, _)| (i, i + 1)),
+            ),
+            MatcherInner::CodePoint(set) => Box::new(
+                CodePoints::new(units(text))
+                    .filter(move |&(_, _, c)| contains(&set, c, case))
+                    .map(|(start, end, _)| (start, end)),
+            ),
+        }
+    }
+
+    /// Like `rfind_iter`, but the matcher is moved into the iterator.
+    pub(crate) fn into_rfind_iter<'a>(
+        self,
+        text: &'a DynamicString,
+        mode: MatchMode,
+    ) -> Box<dyn Iterator<Item = (usize, usize)> + 'a>
+    where
+        'p: 'a,
+    {
+        let len = text.len();
+        match self.0 {
+            MatcherInner::Str(s) => {
+                let ptn_len = s.len();
+                Box::new(
+                    ReversePatternFinder::with_mode(text, &s, mode).map(move |i| (i, i + ptn_len)),
+                )
+            }
+            MatcherInner::Unit(mut predicate) => Box::new(
+                units_rev(text)
+                    .enumerate()
+                    .filter(move |&(_, c)| predicate(c))
+                    .map(move |(i, _)| (len - i - 1, len - i)),
+            ),
+            MatcherInner::CodePoint(set) => Box::new(
+                CodePoints::new_reverse(units_rev(text), len)
+                    .filter(move |&(_, _, c)| contains(&set, c, CaseSensitivity::Sensitive))
+                    .map(|(start, end, _)| (start, end)),
+            ),
+        }
+    }
+
+    /// Returns the end of the match that starts at `position`, if there is one.
+    #[inline]
+    pub(crate) fn match_at(&mut self, text: &DynamicString, position: usize) -> Option<usize> {
+        self.match_at_with_case(text, position, CaseSensitivity::Sensitive)
+    }
+
+    /// Like `match_at`, but the characters are compared using the given case sensitivity.
+    pub(crate) fn match_at_with_case(
+        &mut self,
+        text: &DynamicString,
+        position: usize,
+        case: CaseSensitivity,
+    ) -> Option<usize> {
+        match &mut self.0 {
+            MatcherInner::Str(s) => {
+                let end = position + s.len();
+                if end <= text.len() && text.slice(position, s.len()).eq_with_case(s, case) {
+                    Some(end)
+                } else {
+                    None
+                }
+            }
+            MatcherInner::Unit(predicate) => match text.char_code_at(position) {
+                Some(c) if predicate(c) => Some(position + 1),
+                _ => None,
+            },
+            MatcherInner::CodePoint(set) => {
+                let c = text.char_code_at(position)?;
+                let (end, code_point) = match text.char_code_at(position + 1) {
+                    Some(next) if is_lead_surrogate(c) && is_trail_surrogate(next) => {
+                        (position + 2, combine_surrogates(c, next))
+                    }
+                    _ => (position + 1, c as u32),
+                };
+                if contains(set, code_point, case) {
+                    Some(end)
+                } else {
+                    None
+                }
+            }
+        }
+    }
+
+    /// Returns the start of the match that ends at `end`, if there is one.
+    pub(crate) fn match_ending_at(&mut self, text: &DynamicString, end: usize) -> Option<usize> {
+        match &mut self.0 {
+            MatcherInner::Str(s) => {
+                let start = end.checked_sub(s.len())?;
+                if end <= text.len() && text.slice(start, s.len()).iter().eq(s.iter()) {
+                    Some(start)
+                } else {
+                    None
+                }
+            }
+            MatcherInner::Unit(predicate) => match text.char_code_at(end.checked_sub(1)?) {
+                Some(c) if predicate(c) => Some(end - 1),
+                _ => None,
+            },
+            MatcherInner::CodePoint(set) => {
+                let c = text.char_code_at(end.checked_sub(1)?)?;
+                let prev = end.checked_sub(2).and_then(|i| text.char_code_at(i));
+                let (start, code_point) = match prev {
+                    Some(prev) if is_lead_surrogate(prev) && is_trail_surrogate(c) => {
+                        (end - 2, combine_surrogates(prev, c))
+                    }
+                    _ => (end - 1, c as u32),
+                };
+                if contains(set, code_point, CaseSensitivity::Sensitive) {
+                    Some(start)
+                } else {
+                    None
+                }
+            }
+        }
+    }
+}
+
+impl<'p> fmt::Debug for Matcher<'p> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.0 {
+            MatcherInner::Str(s) => f.debug_tuple("Str").field(s).finish(),
+            MatcherInner::Unit(_) => f.write_str("Unit(..)"),
+            MatcherInner::CodePoint(set) => f.debug_tuple("CodePoint").field(set).finish(),
+        }
+    }
+}
+
+#[inline]
+fn contains(set: &[char], code_point: u32, case: CaseSensitivity) -> bool {
+    let code_point = case.fold_code_point(code_point);
+    set.iter()
+        .any(|&c| case.fold_code_point(c as u32) == code_point)
+}
+
+#[inline]
+fn combine_surrogates(lead: u16, trail: u16) -> u32 {
+    0x10000 + (((lead as u32) & 0x3ff) << 10) + ((trail as u32) & 0x3ff)
+}
+
+/// Returns the characters of the string, in order.
+pub(crate) fn units(text: &DynamicString) -> impl Iterator<Item = u16> + '_ {
+    text.chunks()
+        .flat_map(|chunk| (0..chunk.len()).map(move |i| chunk.get(i).unwrap()))
+}
+
+/// Returns the characters of the string, from the last one to the first one.
+fn units_rev(text: &DynamicString) -> impl Iterator<Item = u16> + '_ {
+    text.rchunks()
+        .flat_map(|chunk| (0..chunk.len()).rev().map(move |i| chunk.get(i).unwrap()))
+}
+
+/// Iterator over the `(start, end, code_point)` of the code points of a sequence of
+/// characters, where an unpaired surrogate is a code point on its own.
+struct CodePoints<I: Iterator<Item = u16>> {
+    units: std::iter::Peekable<I>,
+    /// Offset of the next character, or of the end of the next character when reversed.
+    offset: usize,
+    reverse: bool,
+}
+
+impl<I: Iterator<Item = u16>> CodePoints<I> {
+    fn new(units: I) -> Self {
+        CodePoints {
+            units: units.peekable(),
+            offset: 0,
+            reverse: false,
+        }
+    }
+
+    /// Creates the iterator over the characters of a string of the given length, which are
+    /// given from the last one to the first one.
+    fn new_reverse(units: I, len: usize) -> Self {
+        CodePoints {
+            units: units.peekable(),
+            offset: len,
+            reverse: true,
+        }
+    }
+}
+
+impl<I: Iterator<Item = u16>> Iterator for CodePoints<I> {
+    type Item = (usize, usize, u32);
+
+    fn next(&mut self) -> Option<(usize, usize, u32)> {
+        let c = self.units.next()?;
+        let (first, second) = if self.reverse {
+            (self.units.peek().copied(), Some(c))
+        } else {
+            (Some(c), self.units.peek().copied())
+        };
+
+        let (width, code_point) = match (first, second) {
+            (Some(lead), Some(trail)) if is_lead_surrogate(lead) && is_trail_surrogate(trail) => {
+                self.units.next();
+                (2, combine_surrogates(lead, trail))
+            }
+            _ => (1, c as u32),
+        };
+
+        if self.reverse {
+            self.offset -= width;
+            Some((self.offset, self.offset + width, code_point))
+        } else {
+            self.offset += width;
+            Some((self.offset - width, self.offset, code_point))
+        }
+    }
+}
+
+/// Returns true if the character is a white space or a line terminator in JavaScript.
+pub(crate) fn is_whitespace(c: u16) -> bool {
+    matches!(
+        c,
+        0x09..=0x0d
+            | 0x20
+            | 0xa0
+            | 0x1680
+            | 0x2000..=0x200a
+            | 0x2028
+            | 0x2029
+            | 0x202f
+            | 0x205f
+            | 0x3000
+            | 0xfeff
+    )
+}
+
+/// The algorithm used by a PatternFinder, `PatternFinder::with_mode` chooses one based on the
+/// text and the pattern.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SearchAlgorithm {
+    /// Knuth-Morris-Pratt, which never looks at a character of the text twice.
+    Kmp,
+    /// Boyer-Moore-Horspool, which skips parts of the text using the last character of each
+    /// window, fast for longer patterns.
+    Horspool,
+    /// A scan for a single character which compares a word of one-byte characters at a time,
+    /// patterns that are longer than one character use Horspool instead.
+    ByteScan,
+}
+
+impl SearchAlgorithm {
+    /// Returns the algorithm that is expected to be the fastest for searching the pattern in
+    /// the text, based on the length of the pattern and the encoding of both strings.
+    /// ```
+    /// use dynstr::{DynamicString, SearchAlgorithm};
+    /// let ascii = DynamicString::new("The quick brown fox");
+    /// let cyrillic = DynamicString::new("Съешь же ещё этих мягких");
+    /// assert_eq!(SearchAlgorithm::select(&ascii, &"fox".into()), SearchAlgorithm::Kmp);
+    /// assert_eq!(SearchAlgorithm::select(&cyrillic, &"ещё".into()), SearchAlgorithm::Horspool);
+    /// ```
+    pub fn select(text: &DynamicString, pattern: &DynamicString) -> Self {
+        let one_byte_text = text.has_one_byte_char();
+        match (pattern.len(), pattern.has_one_byte_char()) {
+            (1, _) => SearchAlgorithm::ByteScan,
+            // The pattern can only be found around the boundaries of the one-byte chunks,
+            // Horspool skips the rest of each chunk at once.
+            (_, false) if one_byte_text => SearchAlgorithm::Horspool,
+            (n, _) if one_byte_text && n >= HORSPOOL_MIN_LENGTH => SearchAlgorithm::Horspool,
+            (n, _) if !one_byte_text && n >= HORSPOOL_MIN_LENGTH_TWO_BYTE => {
+                SearchAlgorithm::Horspool
+            }
+            _ => SearchAlgorithm::Kmp,
+        }
+    }
+}
 
 /// A lazy (iterative) string matcher.
+/// ```
+/// use dynstr::{DynamicString, MatchMode, PatternFinder};
+/// let text = DynamicString::new("aaaa");
+/// let pattern = DynamicString::new("aa");
+/// let all: Vec<usize> = PatternFinder::new(&text, &pattern).collect();
+/// assert_eq!(all, vec![0, 1, 2]);
+/// let all: Vec<usize> = PatternFinder::with_mode(&text, &pattern, MatchMode::NonOverlapping).collect();
+/// assert_eq!(all, vec![0, 2]);
+/// ```
+///
+/// The text is borrowed and read once from start to end, only the pattern is copied.
 #[derive(Debug, Clone)]
-pub struct PatternFinder(PatternFinderInner);
+pub struct PatternFinder<'a>(PatternFinderInner<'a>);
+
+impl<'a> PatternFinder<'a> {
+    /// Creates a new PatternFinder which will search for the given `pattern` in the given
+    /// `text`, reporting overlapping matches.
+    #[inline]
+    pub fn new(text: &'a DynamicString, pattern: &DynamicString) -> Self {
+        Self::with_mode(text, pattern, MatchMode::Overlapping)
+    }
 
-impl PatternFinder {
     /// Creates a new PatternFinder which will search for the given `pattern` in the given
-    /// `text`.
-    pub fn new(text: DynamicString, pattern: DynamicString) -> Self {
+    /// `text` using the given mode.
+    ///
+    /// An empty pattern matches at every position from zero to the length of the text,
+    /// inclusive.
+    pub fn with_mode(text: &'a DynamicString, pattern: &DynamicString, mode: MatchMode) -> Self {
+        Self::with_algorithm(text, pattern, mode, SearchAlgorithm::select(text, pattern))
+    }
+
+    /// Creates a new PatternFinder which uses the given algorithm, regardless of the pattern.
+    pub fn with_algorithm(
+        text: &'a DynamicString,
+        pattern: &DynamicString,
+        mode: MatchMode,
+        algorithm: SearchAlgorithm,
+    ) -> Self {
         let txt_len = text.len();
         let ptn_len = pattern.len();
 
         PatternFinder(match (txt_len, ptn_len) {
-            (0, 0) => PatternFinderInner::Zero { done: false },
-            (_, 0) => PatternFinderInner::Any {
-                index: 0,
-                end: txt_len,
+            (_, 0) => PatternFinderInner::Any { range: 0..=txt_len },
+            _ if ptn_len > txt_len => PatternFinderInner::Zero { done: true },
+            _ if ptn_len == txt_len => PatternFinderInner::Zero {
+                done: text != pattern,
             },
-            (0, _) => PatternFinderInner::Zero { done: false },
+            _ if algorithm == SearchAlgorithm::Kmp => {
+                PatternFinderInner::Kmp(KMPPatternFinder::new(text, pattern, mode))
+            }
+            _ => PatternFinderInner::Chunked(Box::new(ChunkedPatternFinder::new(
+                text,
+                Searcher::new(pattern.iter().collect()),
+                mode,
+            ))),
+        })
+    }
+
+    /// Creates a new PatternFinder which compares the characters using the given case
+    /// sensitivity, the case-insensitive search always uses KMP.
+    /// ```
+    /// use dynstr::{CaseSensitivity, DynamicString, MatchMode, PatternFinder};
+    /// let text = DynamicString::new("Fast, FASTER, fastest");
+    /// let pattern = DynamicString::new("fast");
+    /// let case = CaseSensitivity::IgnoreAsciiCase;
+    /// let all: Vec<usize> =
+    ///     PatternFinder::with_case(&text, &pattern, MatchMode::NonOverlapping, case).collect();
+    /// assert_eq!(all, vec![0, 6, 14]);
+    /// ```
+    pub fn with_case(
+        text: &'a DynamicString,
+        pattern: &DynamicString,
+        mode: MatchMode,
+        case: CaseSensitivity,
+    ) -> Self {
+        if case == CaseSensitivity::Sensitive {
+            return Self::with_mode(text, pattern, mode);
+        }
+
+        let txt_len = text.len();
+        let ptn_len = pattern.len();
+
+        PatternFinder(match (txt_len, ptn_len) {
+            (_, 0) => PatternFinderInner::Any { range: 0..=txt_len },
             _ if ptn_len > txt_len => PatternFinderInner::Zero { done: true },
             _ if ptn_len == txt_len => PatternFinderInner::Zero {
-                done: !text.eq(&pattern),
+                done: !text.eq_with_case(pattern, case),
             },
-            _ => PatternFinderInner::KMP(KMPPatternFinder::new(text, pattern)),
+            _ => PatternFinderInner::Kmp(KMPPatternFinder::with_case(text, pattern, mode, case)),
         })
     }
 
-    /// Returns a vector containing index of all the occurrences.
+    /// Returns a vector containing index of all the occurrences, including the overlapping
+    /// ones.
+    #[inline]
+    pub fn all(text: &DynamicString, pattern: &DynamicString) -> Vec<usize> {
+        PatternFinder::new(text, pattern).collect()
+    }
+}
+
+impl<'a> Iterator for PatternFinder<'a> {
+    type Item = usize;
+
+    #[inline]
+    fn next(&mut self) -> Option<usize> {
+        self.0.next()
+    }
+}
+
+/// A lazy (iterative) string matcher which searches from the end of the text, and yields the
+/// positions of the matches in descending order.
+/// ```
+/// use dynstr::{DynamicString, MatchMode, ReversePatternFinder};
+/// let text = DynamicString::new("aaaa");
+/// let pattern = DynamicString::new("aa");
+/// let all: Vec<usize> = ReversePatternFinder::new(&text, &pattern).collect();
+/// assert_eq!(all, vec![2, 1, 0]);
+/// let text = DynamicString::new("aaa");
+/// let all: Vec<usize> =
+///     ReversePatternFinder::with_mode(&text, &pattern, MatchMode::NonOverlapping).collect();
+/// assert_eq!(all, vec![1]);
+/// ```
+#[derive(Debug, Clone)]
+pub struct ReversePatternFinder<'a>(PatternFinderInner<'a>);
+
+impl<'a> ReversePatternFinder<'a> {
+    /// Creates a new ReversePatternFinder which will search for the given `pattern` in the
+    /// given `text`, reporting overlapping matches.
     #[inline]
-    pub fn all(text: DynamicString, pattern: DynamicString) -> Vec<usize> {
-        Self::new(text, pattern).collect()
+    pub fn new(text: &'a DynamicString, pattern: &DynamicString) -> Self {
+        Self::with_mode(text, pattern, MatchMode::Overlapping)
+    }
+
+    /// Creates a new ReversePatternFinder which will search for the given `pattern` in the
+    /// given `text` using the given mode, in the non-overlapping mode the search continues
+    /// before the start of the previous match.
+    ///
+    /// An empty pattern matches at every position from the length of the text to zero.
+    pub fn with_mode(text: &'a DynamicString, pattern: &DynamicString, mode: MatchMode) -> Self {
+        let txt_len = text.len();
+        let ptn_len = pattern.len();
+
+        ReversePatternFinder(match (txt_len, ptn_len) {
+            (_, 0) => PatternFinderInner::AnyReverse { range: 0..=txt_len },
+            _ if ptn_len > txt_len => PatternFinderInner::Zero { done: true },
+            _ if ptn_len == txt_len => PatternFinderInner::Zero {
+                done: text != pattern,
+            },
+            _ => PatternFinderInner::Kmp(KMPPatternFinder::new_reverse(text, pattern, mode)),
+        })
     }
 }
 
-impl Iterator for PatternFinder {
+impl<'a> Iterator for ReversePatternFinder<'a> {
     type Item = usize;
 
     #[inline]
@@ -42,17 +640,271 @@ impl Iterator for PatternFinder {
     }
 }
 
+/// An iterator over the non-overlapping matches of a pattern in a string, created by
+/// `DynamicString::matches`.
+pub struct Matches<'a> {
+    text: &'a DynamicString,
+    inner: Box<dyn Iterator<Item = (usize, usize)> + 'a>,
+}
+
+impl<'a> Matches<'a> {
+    #[inline]
+    pub(crate) fn new(text: &'a DynamicString, matcher: Matcher<'a>) -> Self {
+        Matches {
+            text,
+            inner: matcher.into_find_iter(text, MatchMode::NonOverlapping),
+        }
+    }
+}
+
+impl<'a> Iterator for Matches<'a> {
+    type Item = DynamicString;
+
+    #[inline]
+    fn next(&mut self) -> Option<DynamicString> {
+        let (start, end) = self.inner.next()?;
+        Some(self.text.slice(start, end - start))
+    }
+}
+
+impl<'a> fmt::Debug for Matches<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Matches").field("text", self.text).finish()
+    }
+}
+
+/// An iterator over the non-overlapping matches of a pattern in a string and their positions,
+/// created by `DynamicString::match_indices`.
+#[derive(Debug)]
+pub struct MatchIndices<'a>(Matches<'a>);
+
+impl<'a> MatchIndices<'a> {
+    #[inline]
+    pub(crate) fn new(text: &'a DynamicString, matcher: Matcher<'a>) -> Self {
+        MatchIndices(Matches::new(text, matcher))
+    }
+}
+
+impl<'a> Iterator for MatchIndices<'a> {
+    type Item = (usize, DynamicString);
+
+    #[inline]
+    fn next(&mut self) -> Option<(usize, DynamicString)> {
+        let (start, end) = self.0.inner.next()?;
+        Some((start, self.0.text.slice(start, end - start)))
+    }
+}
+
+/// A lazy iterator over the substrings of a string that are separated by the matches of a
+/// pattern, created by `DynamicString::split_iter`.
+/// ```
+/// use dynstr::DynamicString;
+/// let line = DynamicString::new("id,name,email,phone,address");
+/// let mut fields = line.split_iter(",");
+/// assert_eq!(fields.next().unwrap(), "id");
+/// assert_eq!(fields.next().unwrap(), "name");
+/// ```
+pub struct Split<'a> {
+    text: &'a DynamicString,
+    inner: Box<dyn Iterator<Item = (usize, usize)> + 'a>,
+    /// Whether the separator is empty, in which case the matches at the ends are skipped.
+    empty: bool,
+    /// The end of the last match, or its start when the string is split from the end.
+    last_index: usize,
+    reverse: bool,
+    finished: bool,
+}
+
+impl<'a> Split<'a> {
+    pub(crate) fn new(text: &'a DynamicString, matcher: Matcher<'a>, reverse: bool) -> Self {
+        let empty = matcher.max_len() == 0;
+        let (inner, last_index) = if reverse {
+            (
+                matcher.into_rfind_iter(text, MatchMode::NonOverlapping),
+                text.len(),
+            )
+        } else {
+            (matcher.into_find_iter(text, MatchMode::NonOverlapping), 0)
+        };
+        Split {
+            text,
+            inner,
+            empty,
+            last_index,
+            reverse,
+            finished: false,
+        }
+    }
+}
+
+impl<'a> Iterator for Split<'a> {
+    type Item = DynamicString;
+
+    fn next(&mut self) -> Option<DynamicString> {
+        if self.finished {
+            return None;
+        }
+
+        let len = self.text.len();
+        for (start, end) in self.inner.by_ref() {
+            let last_index = self.last_index;
+            if self.reverse {
+                self.last_index = start;
+                // Like in JavaScript an empty separator does not produce a substring before
+                // the first character, or after the last one.
+                if !(self.empty && last_index == len && start == len) {
+                    return Some(self.text.slice(end, last_index - end));
+                }
+            } else {
+                self.last_index = end;
+                if !(self.empty && last_index == 0 && start == 0) {
+                    return Some(self.text.slice(last_index, start - last_index));
+                }
+            }
+        }
+
+        self.finished = true;
+
+        // The rest of the string is always added unless the separator is empty, in which
+        // case the last match is at the end and every character is already added.
+        if self.reverse && (!self.empty || self.last_index > 0) {
+            Some(self.text.slice(0, self.last_index))
+        } else if !self.reverse && (!self.empty || self.last_index < len) {
+            Some(self.text.slice(self.last_index, len - self.last_index))
+        } else {
+            None
+        }
+    }
+}
+
+impl<'a> fmt::Debug for Split<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Split")
+            .field("text", self.text)
+            .field("last_index", &self.last_index)
+            .field("reverse", &self.reverse)
+            .finish()
+    }
+}
+
+/// A string matcher for text that arrives in pieces, the state of the matcher is kept between
+/// the calls to `feed` so matches that straddle the pieces are found without searching the
+/// previous pieces again.
+/// ```
+/// use dynstr::{DynamicString, StreamingFinder};
+/// let mut finder = StreamingFinder::new(&DynamicString::new("world"));
+/// assert_eq!(finder.feed(&DynamicString::new("Hello wor")), vec![]);
+/// assert_eq!(finder.feed(&DynamicString::new("ld, the world")), vec![6, 17]);
+/// assert_eq!(finder.offset(), 22);
+/// ```
+#[derive(Debug, Clone)]
+pub struct StreamingFinder {
+    /// The matcher, or `None` when the pattern is empty.
+    state: Option<KmpState>,
+    /// Number of characters that are fed so far.
+    offset: usize,
+    /// Whether any character is fed, an empty pattern also matches before the first one.
+    started: bool,
+}
+
+impl StreamingFinder {
+    /// Creates a new StreamingFinder which reports the overlapping matches of the `pattern`.
+    #[inline]
+    pub fn new(pattern: &DynamicString) -> Self {
+        Self::with_mode(pattern, MatchMode::Overlapping)
+    }
+
+    /// Creates a new StreamingFinder which reports the matches of the `pattern` using the
+    /// given mode.
+    ///
+    /// An empty pattern matches at every position, including zero and the end of the text.
+    pub fn with_mode(pattern: &DynamicString, mode: MatchMode) -> Self {
+        let state = if pattern.is_empty() {
+            None
+        } else {
+            Some(KmpState::new(pattern.iter().collect(), mode))
+        };
+        StreamingFinder {
+            state,
+            offset: 0,
+            started: false,
+        }
+    }
+
+    /// Returns the number of characters that are fed to the finder.
+    #[inline]
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// Searches the next piece of the text, and returns the positions of the matches that end
+    /// in it, relative to the start of the first piece.
+    pub fn feed(&mut self, chunk: &DynamicString) -> Vec<usize> {
+        let start = self.offset;
+        self.offset += chunk.len();
+
+        let state = match &mut self.state {
+            Some(state) => state,
+            None => {
+                let first = if self.started { start + 1 } else { start };
+                self.started = true;
+                return (first..=self.offset).collect();
+            }
+        };
+
+        let ptn_len = state.pattern.len();
+        let mut result = Vec::new();
+        let mut end = start;
+        for slice in chunk.chunks() {
+            match slice {
+                ChunkSlice::OneByte(s) => {
+                    for &c in s {
+                        end += 1;
+                        if state.feed(c as u16) {
+                            result.push(end - ptn_len);
+                        }
+                    }
+                }
+                ChunkSlice::TwoByte(s) => {
+                    for &c in s {
+                        end += 1;
+                        if state.feed(c) {
+                            result.push(end - ptn_len);
+                        }
+                    }
+                }
+            }
+        }
+
+        result
+    }
+
+    /// Forgets the text that is fed so far, so the finder can be used for a new text.
+    #[inline]
+    pub fn reset(&mut self) {
+        if let Some(state) = &mut self.state {
+            state.matched = 0;
+        }
+        self.offset = 0;
+        self.started = false;
+    }
+}
+
 #[derive(Debug, Clone)]
-enum PatternFinderInner {
+enum PatternFinderInner<'a> {
     /// This finder will ony yield one 0 and finish.
     Zero { done: bool },
-    /// Yield all the number until the end.
-    Any { index: usize, end: usize },
+    /// Yield all the numbers in the range.
+    Any { range: RangeInclusive<usize> },
+    /// Yield all the numbers in the range, from the end.
+    AnyReverse { range: RangeInclusive<usize> },
     /// Use KMP finder.
-    KMP(KMPPatternFinder),
+    Kmp(KMPPatternFinder<'a>),
+    /// Search each chunk of the text on its own.
+    Chunked(Box<ChunkedPatternFinder<'a>>),
 }
 
-impl Iterator for PatternFinderInner {
+impl<'a> Iterator for PatternFinderInner<'a> {
     type Item = usize;
 
     #[inline]
@@ -66,106 +918,194 @@ impl Iterator for PatternFinderInner {
                     Some(0)
                 }
             }
-            PatternFinderInner::Any { index, end } => {
-                if index == end {
-                    None
-                } else {
-                    let c = *index;
-                    *index += 1;
-                    Some(c)
-                }
-            }
-            PatternFinderInner::KMP(finder) => finder.next(),
+            PatternFinderInner::Any { range } => range.next(),
+            PatternFinderInner::AnyReverse { range } => range.next_back(),
+            PatternFinderInner::Kmp(finder) => finder.next(),
+            PatternFinderInner::Chunked(finder) => finder.next(),
         }
     }
 }
 
 #[derive(Debug, Clone)]
-struct KMPPatternFinder {
-    text: IndexedString,
-    pattern: IndexedString,
-    lps_array: Option<Vec<usize>>,
-    // iterator state
-    done: bool,
-    text_index: usize,
-    pattern_index: usize,
+struct KMPPatternFinder<'a> {
+    chunks: DynamicStringChunks<'a>,
+    /// The chunk that is being scanned and the number of its characters that are scanned.
+    current: ChunkSlice<'a>,
+    chunk_index: usize,
+    /// Number of characters in the chunks before the current one.
+    offset: usize,
+    /// When searching backward this is the length of the text, and the chunks and the
+    /// pattern are visited in reverse.
+    reverse: Option<usize>,
+    /// The characters of the text are folded before they are compared.
+    case: CaseSensitivity,
+    state: KmpState,
 }
 
-impl KMPPatternFinder {
+impl<'a> KMPPatternFinder<'a> {
     #[inline]
-    pub fn new(text: DynamicString, pattern: DynamicString) -> Self {
-        assert!(text.len() > 0);
-        assert!(pattern.len() > 0);
+    pub fn new(text: &'a DynamicString, pattern: &DynamicString, mode: MatchMode) -> Self {
+        assert!(!text.is_empty());
+        assert!(!pattern.is_empty());
         KMPPatternFinder {
-            text: IndexedString::new(text),
-            pattern: IndexedString::new(pattern),
-            lps_array: None,
-            done: false,
-            text_index: 0,
-            pattern_index: 0,
+            chunks: text.chunks(),
+            current: ChunkSlice::OneByte(&[]),
+            chunk_index: 0,
+            offset: 0,
+            reverse: None,
+            case: CaseSensitivity::Sensitive,
+            state: KmpState::new(pattern.iter().collect(), mode),
         }
     }
-}
-
-impl Iterator for KMPPatternFinder {
-    type Item = usize;
 
+    /// Creates a finder that compares the characters using the given case sensitivity.
     #[inline]
-    fn next(&mut self) -> Option<usize> {
-        if self.done {
-            return None;
+    pub fn with_case(
+        text: &'a DynamicString,
+        pattern: &DynamicString,
+        mode: MatchMode,
+        case: CaseSensitivity,
+    ) -> Self {
+        let pattern = DynamicString::from_units(pattern.iter().map(|c| case.fold(c)).collect());
+        KMPPatternFinder {
+            case,
+            ..KMPPatternFinder::new(text, &pattern, mode)
         }
+    }
 
-        let text = &self.text;
-        let pattern = &self.pattern;
-        let lps = self
-            .lps_array
-            .get_or_insert_with(|| compute_lps_array(pattern));
-        let len = text.len();
-        let ptn_len = pattern.len();
-
-        let mut i = self.text_index;
-        let mut j = self.pattern_index;
+    /// Creates a finder that searches from the end of the text to its start.
+    #[inline]
+    pub fn new_reverse(text: &'a DynamicString, pattern: &DynamicString, mode: MatchMode) -> Self {
+        assert!(!text.is_empty());
+        assert!(!pattern.is_empty());
+        let mut pattern: Vec<u16> = pattern.iter().collect();
+        pattern.reverse();
+        KMPPatternFinder {
+            chunks: text.rchunks(),
+            current: ChunkSlice::OneByte(&[]),
+            chunk_index: 0,
+            offset: 0,
+            reverse: Some(text.len()),
+            case: CaseSensitivity::Sensitive,
+            state: KmpState::new(pattern, mode),
+        }
+    }
+}
 
-        while i < len {
-            if pattern.at(j) == text.at(i) {
-                j += 1;
-                i += 1;
+impl<'a> KMPPatternFinder<'a> {
+    /// Feeds the rest of the current chunk to the matcher, after mapping each character with
+    /// `map`, and returns the number of characters that are consumed until the next match.
+    #[inline(always)]
+    fn scan<F: Fn(u16) -> u16>(&mut self, map: F) -> Option<usize> {
+        let state = &mut self.state;
+        let rest = self.current.len() - self.chunk_index;
+        match (self.current, self.reverse) {
+            (ChunkSlice::OneByte(s), None) => s[self.chunk_index..]
+                .iter()
+                .position(|&c| state.feed(map(c as u16))),
+            (ChunkSlice::TwoByte(s), None) => s[self.chunk_index..]
+                .iter()
+                .position(|&c| state.feed(map(c))),
+            (ChunkSlice::OneByte(s), Some(_)) => s[..rest]
+                .iter()
+                .rev()
+                .position(|&c| state.feed(map(c as u16))),
+            (ChunkSlice::TwoByte(s), Some(_)) => {
+                s[..rest].iter().rev().position(|&c| state.feed(map(c)))
             }
+        }
+    }
+}
 
-            if j == ptn_len {
-                self.text_index = i;
-                self.pattern_index = lps[j - 1];
-                return Some(i - j);
-            }
+impl<'a> Iterator for KMPPatternFinder<'a> {
+    type Item = usize;
 
-            if i < len && pattern.at(j) != text.at(i) {
-                if j != 0 {
-                    j = lps[j - 1];
-                } else {
-                    i += 1;
+    #[inline]
+    fn next(&mut self) -> Option<usize> {
+        loop {
+            let found = match self.case {
+                CaseSensitivity::Sensitive => self.scan(|c| c),
+                CaseSensitivity::IgnoreAsciiCase => self.scan(fold_ascii),
+                CaseSensitivity::IgnoreCase => self.scan(fold),
+            };
+
+            match found {
+                Some(n) => {
+                    self.chunk_index += n + 1;
+                    let scanned = self.offset + self.chunk_index;
+                    return Some(match self.reverse {
+                        None => scanned - self.state.pattern.len(),
+                        Some(len) => len - scanned,
+                    });
+                }
+                None => {
+                    self.offset += self.current.len();
+                    self.current = self.chunks.next()?;
+                    self.chunk_index = 0;
                 }
             }
         }
+    }
+}
 
-        self.done = true;
-        None
+/// The state of a KMP matcher, which is fed one character at a time.
+#[derive(Debug, Clone)]
+struct KmpState {
+    pattern: Vec<u16>,
+    lps: Vec<usize>,
+    mode: MatchMode,
+    /// Number of characters of the pattern that are matched by the end of the text so far.
+    matched: usize,
+}
+
+impl KmpState {
+    fn new(pattern: Vec<u16>, mode: MatchMode) -> Self {
+        let lps = compute_lps_array(&pattern);
+        KmpState {
+            pattern,
+            lps,
+            mode,
+            matched: 0,
+        }
+    }
+
+    /// Advances the matcher by one character, returns true if a match ends with it.
+    #[inline(always)]
+    fn feed(&mut self, c: u16) -> bool {
+        let mut j = self.matched;
+        while j > 0 && self.pattern[j] != c {
+            j = self.lps[j - 1];
+        }
+        if self.pattern[j] == c {
+            j += 1;
+        }
+
+        if j == self.pattern.len() {
+            self.matched = match self.mode {
+                MatchMode::Overlapping => self.lps[j - 1],
+                MatchMode::NonOverlapping => 0,
+            };
+            true
+        } else {
+            self.matched = j;
+            false
+        }
     }
 }
 
-#[inline(always)]
-fn compute_lps_array(pattern: &IndexedString) -> Vec<usize> {
+/// Computes the length of the longest proper prefix of the pattern that is also a suffix of
+/// `pattern[..=i]`, for every `i`.
+fn compute_lps_array(pattern: &[u16]) -> Vec<usize> {
     let ptn_len = pattern.len();
     let mut lps = vec![0; ptn_len];
 
     // length of the previous longest prefix suffix
     let mut len = 0;
-    lps[0] = 0;
 
     // the loop calculates lps[i] for i = 1 to ptn_len-1
     let mut i = 1;
     while i < ptn_len {
-        if pattern.at(i) == pattern.at(len) {
+        if pattern[i] == pattern[len] {
             len += 1;
             lps[i] = len;
             i += 1;
@@ -179,3 +1119,275 @@ fn compute_lps_array(pattern: &IndexedString) -> Vec<usize> {
 
     lps
 }
+
+/// Finds the matches inside of every chunk of the text using a Searcher, and the matches that
+/// span multiple chunks using the characters that are kept from the previous chunks.
+#[derive(Debug, Clone)]
+struct ChunkedPatternFinder<'a> {
+    chunks: DynamicStringChunks<'a>,
+    /// The chunk that is being searched and the index where the search continues.
+    current: ChunkSlice<'a>,
+    chunk_index: usize,
+    /// Offset of the start of the current chunk in the text.
+    offset: usize,
+    searcher: Searcher,
+    mode: MatchMode,
+    /// The last `pattern.len() - 1` characters before the current chunk.
+    tail: Vec<u16>,
+    /// Buffer for the characters around the boundary of two chunks.
+    window: Vec<u16>,
+    /// Matches that start before the current chunk and end in it.
+    pending: VecDeque<usize>,
+    /// In the non-overlapping mode matches that start before this offset are skipped.
+    min_start: usize,
+}
+
+impl<'a> ChunkedPatternFinder<'a> {
+    fn new(text: &'a DynamicString, searcher: Searcher, mode: MatchMode) -> Self {
+        let overlap = searcher.len() - 1;
+        ChunkedPatternFinder {
+            chunks: text.chunks(),
+            current: ChunkSlice::OneByte(&[]),
+            chunk_index: 0,
+            offset: 0,
+            searcher,
+            mode,
+            tail: Vec::with_capacity(overlap),
+            window: Vec::with_capacity(overlap * 2),
+            pending: VecDeque::new(),
+            min_start: 0,
+        }
+    }
+
+    /// Moves to the next chunk and finds the matches that end in it but start before it,
+    /// returns false at the end of the text.
+    fn next_chunk(&mut self) -> bool {
+        let next = match self.chunks.next() {
+            Some(chunk) => chunk,
+            None => return false,
+        };
+
+        let overlap = self.searcher.len() - 1;
+        let current_len = self.current.len();
+        if overlap > 0 {
+            if current_len >= overlap {
+                self.tail.clear();
+            }
+            let from = current_len.saturating_sub(overlap);
+            let current = self.current;
+            self.tail
+                .extend((from..current_len).map(|i| current.get(i).unwrap()));
+            if self.tail.len() > overlap {
+                self.tail.drain(..self.tail.len() - overlap);
+            }
+
+            self.window.clear();
+            self.window.extend_from_slice(&self.tail);
+            self.window
+                .extend((0..cmp::min(overlap, next.len())).map(|i| next.get(i).unwrap()));
+
+            let tail_offset = self.offset + current_len - self.tail.len();
+            let mut from = 0;
+            while let Some(index) = self.searcher.find(&self.window, from) {
+                if index >= self.tail.len() {
+                    break;
+                }
+                self.pending.push_back(tail_offset + index);
+                from = index + 1;
+            }
+        }
+
+        self.offset += current_len;
+        self.current = next;
+        self.chunk_index = 0;
+        true
+    }
+}
+
+impl<'a> Iterator for ChunkedPatternFinder<'a> {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        loop {
+            let index = match self.pending.pop_front() {
+                Some(index) => index,
+                None => match self.searcher.find_in(self.current, self.chunk_index) {
+                    Some(index) => {
+                        self.chunk_index = index + 1;
+                        self.offset + index
+                    }
+                    None if self.next_chunk() => continue,
+                    None => return None,
+                },
+            };
+
+            if index >= self.min_start {
+                if self.mode == MatchMode::NonOverlapping {
+                    self.min_start = index + self.searcher.len();
+                    self.chunk_index =
+                        cmp::max(self.chunk_index, self.min_start.saturating_sub(self.offset));
+                }
+                return Some(index);
+            }
+        }
+    }
+}
+
+/// Finds a pattern in a contiguous sequence of characters.
+#[derive(Debug, Clone)]
+enum Searcher {
+    ByteScan(u16),
+    Horspool(Box<Horspool>),
+}
+
+impl Searcher {
+    fn new(pattern: Vec<u16>) -> Self {
+        if pattern.len() == 1 {
+            Searcher::ByteScan(pattern[0])
+        } else {
+            Searcher::Horspool(Box::new(Horspool::new(pattern)))
+        }
+    }
+
+    /// Returns the length of the pattern.
+    #[inline]
+    fn len(&self) -> usize {
+        match self {
+            Searcher::ByteScan(_) => 1,
+            Searcher::Horspool(horspool) => horspool.pattern.len(),
+        }
+    }
+
+    #[inline]
+    fn find_in(&self, chunk: ChunkSlice, from: usize) -> Option<usize> {
+        match chunk {
+            ChunkSlice::OneByte(s) => self.find(s, from),
+            ChunkSlice::TwoByte(s) => self.find(s, from),
+        }
+    }
+
+    /// Returns the index of the first match at or after `from`.
+    #[inline]
+    fn find<T: Unit>(&self, haystack: &[T], from: usize) -> Option<usize> {
+        let rest = &haystack[cmp::min(from, haystack.len())..];
+        match self {
+            Searcher::ByteScan(c) => T::position(rest, *c),
+            // A pattern with two-byte characters is never found in a one-byte chunk.
+            Searcher::Horspool(horspool) if T::ONE_BYTE && !horspool.one_byte => None,
+            Searcher::Horspool(horspool) => horspool.find(rest),
+        }
+        .map(|index| from + index)
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Horspool {
+    pattern: Vec<u16>,
+    /// How far the window can move based on its last character, indexed by the low byte of
+    /// the character, so characters that share a low byte use the smallest of their shifts.
+    shift: [usize; 256],
+    /// True if the pattern only has one-byte characters.
+    one_byte: bool,
+}
+
+impl Horspool {
+    fn new(pattern: Vec<u16>) -> Self {
+        let last = pattern.len() - 1;
+        let mut shift = [pattern.len(); 256];
+        for (i, &c) in pattern[..last].iter().enumerate() {
+            shift[(c & 0xff) as usize] = last - i;
+        }
+        let one_byte = pattern.iter().all(|&c| c <= 0xff);
+        Horspool {
+            pattern,
+            shift,
+            one_byte,
+        }
+    }
+
+    #[inline]
+    fn find<T: Unit>(&self, haystack: &[T]) -> Option<usize> {
+        let pattern = &self.pattern[..];
+        let len = pattern.len();
+        let last = len - 1;
+        let last_char = pattern[last];
+
+        let mut i = 0;
+        while i + len <= haystack.len() {
+            let c = haystack[i + last].unit();
+            if c == last_char
+                && haystack[i..i + last]
+                    .iter()
+                    .zip(pattern)
+                    .all(|(a, &b)| a.unit() == b)
+            {
+                return Some(i);
+            }
+            i += self.shift[(c & 0xff) as usize];
+        }
+        None
+    }
+}
+
+/// A character in a chunk of a string.
+trait Unit: Copy {
+    const ONE_BYTE: bool;
+
+    fn unit(self) -> u16;
+
+    /// Returns the index of the first occurrence of `c`.
+    fn position(haystack: &[Self], c: u16) -> Option<usize>;
+}
+
+impl Unit for u8 {
+    const ONE_BYTE: bool = true;
+
+    #[inline(always)]
+    fn unit(self) -> u16 {
+        self as u16
+    }
+
+    #[inline]
+    fn position(haystack: &[u8], c: u16) -> Option<usize> {
+        if c > 0xff {
+            None
+        } else {
+            memchr(c as u8, haystack)
+        }
+    }
+}
+
+impl Unit for u16 {
+    const ONE_BYTE: bool = false;
+
+    #[inline(always)]
+    fn unit(self) -> u16 {
+        self
+    }
+
+    #[inline]
+    fn position(haystack: &[u16], c: u16) -> Option<usize> {
+        haystack.iter().position(|&x| x == c)
+    }
+}
+
+/// Returns the index of the first occurrence of `needle`, comparing eight bytes at a time.
+fn memchr(needle: u8, haystack: &[u8]) -> Option<usize> {
+    const LO: u64 = 0x0101_0101_0101_0101;
+    const HI: u64 = 0x8080_8080_8080_8080;
+    let repeated = LO * needle as u64;
+
+    let mut words = haystack.chunks_exact(8);
+    for (i, word) in (&mut words).enumerate() {
+        // Bytes that are equal to the needle become zero.
+        let x = u64::from_le_bytes(word.try_into().unwrap()) ^ repeated;
+        if x.wrapping_sub(LO) & !x & HI != 0 {
+            return word.iter().position(|&b| b == needle).map(|n| i * 8 + n);
+        }
+    }
+
+    let rest = words.remainder();
+    rest.iter()
+        .position(|&b| b == needle)
+        .map(|n| haystack.len() - rest.len() + n)
+}
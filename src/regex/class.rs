@@ -0,0 +1,204 @@
+use crate::case::simple_fold;
+use crate::line::{CR, LF, LINE_SEPARATOR, PARAGRAPH_SEPARATOR};
+
+/// The largest code point.
+const MAX_CODE_POINT: u32 = 0x10ffff;
+
+/// A set of characters, stored as sorted and non-overlapping inclusive ranges.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub(crate) struct CharSet {
+    ranges: Vec<(u32, u32)>,
+}
+
+impl CharSet {
+    #[inline]
+    pub fn new() -> Self {
+        CharSet { ranges: Vec::new() }
+    }
+
+    /// Creates a set from the given ranges, which may be unsorted and overlapping.
+    pub fn from_ranges(ranges: &[(u32, u32)]) -> Self {
+        let mut set = CharSet::new();
+        for &(start, end) in ranges {
+            set.add_range(start, end);
+        }
+        set
+    }
+
+    #[inline]
+    pub fn add(&mut self, c: u32) {
+        self.add_range(c, c);
+    }
+
+    /// Adds the inclusive range to the set, merging it with the adjacent ranges.
+    pub fn add_range(&mut self, start: u32, end: u32) {
+        let i = self
+            .ranges
+            .partition_point(|&(_, e)| e.saturating_add(1) < start);
+        let mut j = i;
+        let (mut start, mut end) = (start, end);
+        while j < self.ranges.len() && self.ranges[j].0 <= end.saturating_add(1) {
+            start = start.min(self.ranges[j].0);
+            end = end.max(self.ranges[j].1);
+            j += 1;
+        }
+        self.ranges.splice(i..j, std::iter::once((start, end)));
+    }
+
+    /// Adds all of the characters of the other set.
+    pub fn extend(&mut self, other: &CharSet) {
+        for &(start, end) in &other.ranges {
+            self.add_range(start, end);
+        }
+    }
+
+    /// Returns the set of the characters that are not in this set.
+    pub fn complement(&self) -> CharSet {
+        let mut ranges = Vec::with_capacity(self.ranges.len() + 1);
+        let mut next = 0;
+        for &(start, end) in &self.ranges {
+            if start > next {
+                ranges.push((next, start - 1));
+            }
+            next = end + 1;
+        }
+        if next <= MAX_CODE_POINT {
+            ranges.push((next, MAX_CODE_POINT));
+        }
+        CharSet { ranges }
+    }
+
+    /// Returns the only character of the set, if it has exactly one.
+    pub fn single(&self) -> Option<u32> {
+        match self.ranges[..] {
+            [(start, end)] if start == end => Some(start),
+            _ => None,
+        }
+    }
+
+    #[inline]
+    pub fn contains(&self, c: u32) -> bool {
+        self.ranges
+            .binary_search_by(|&(start, end)| {
+                if end < c {
+                    std::cmp::Ordering::Less
+                } else if start > c {
+                    std::cmp::Ordering::Greater
+                } else {
+                    std::cmp::Ordering::Equal
+                }
+            })
+            .is_ok()
+    }
+
+    /// Returns true if the set contains a character which is equal to `c` when the case is
+    /// ignored, `c` must be canonicalized.
+    pub fn contains_ignore_case(&self, c: u32, unicode: bool) -> bool {
+        self.contains(c) || variants(c).any(|v| self.contains(v) && canonicalize(v, unicode) == c)
+    }
+}
+
+/// Pairs of lower case characters that have the same upper case, which are not found from
+/// each other using the simple case mappings.
+const CASE_PARTNERS: &[(u32, u32)] = &[
+    (0x6b, 0x212a),
+    (0x73, 0x17f),
+    (0xe5, 0x212b),
+    (0x3b2, 0x3d0),
+    (0x3b5, 0x3f5),
+    (0x3b8, 0x3d1),
+    (0x3b8, 0x3f4),
+    (0x3b9, 0x345),
+    (0x3b9, 0x1fbe),
+    (0x3ba, 0x3f0),
+    (0x3bc, 0xb5),
+    (0x3c0, 0x3d6),
+    (0x3c1, 0x3f1),
+    (0x3c3, 0x3c2),
+    (0x3c6, 0x3d5),
+    (0x1e61, 0x1e9b),
+];
+
+/// Returns the characters that may canonicalize to the same character as `c`.
+fn variants(c: u32) -> impl Iterator<Item = u32> {
+    let ch = char::from_u32(c);
+    let lower = ch.and_then(|ch| single(ch.to_lowercase()));
+    let upper = ch.and_then(|ch| single(ch.to_uppercase()));
+    let upper_lower = upper.and_then(|ch| single(ch.to_lowercase()));
+    let base = lower.or(upper_lower).map_or(c, |ch| ch as u32);
+    let partners = CASE_PARTNERS
+        .iter()
+        .filter(move |&&(a, b)| a == base || b == base)
+        .flat_map(|&(a, b)| [a, b]);
+    IntoIterator::into_iter([lower, upper, upper_lower])
+        .flatten()
+        .map(|ch| ch as u32)
+        .chain(partners)
+}
+
+/// Returns the only character of a case mapping, or `None` if it maps to several characters.
+#[inline]
+fn single<I: Iterator<Item = char>>(mut chars: I) -> Option<char> {
+    let first = chars.next()?;
+    match chars.next() {
+        None => Some(first),
+        Some(_) => None,
+    }
+}
+
+/// Maps a character to the representative of its case-insensitive class, like the
+/// `Canonicalize` operation of ECMAScript. Simple case folding is used in the unicode mode and
+/// the simple upper case mapping otherwise.
+pub(crate) fn canonicalize(c: u32, unicode: bool) -> u32 {
+    if unicode {
+        return simple_fold(c);
+    }
+
+    if c < 0x80 {
+        return if (0x61..=0x7a).contains(&c) {
+            c - 0x20
+        } else {
+            c
+        };
+    }
+    match char::from_u32(c).and_then(|ch| single(ch.to_uppercase())) {
+        // The non-ASCII characters are never mapped to ASCII ones, such as `ſ` to `S`.
+        Some(upper) if (upper as u32) >= 0x80 => upper as u32,
+        _ => c,
+    }
+}
+
+/// Returns true if the character is a line terminator.
+#[inline]
+pub(crate) fn is_line_terminator(c: u32) -> bool {
+    c == LF as u32
+        || c == CR as u32
+        || c == LINE_SEPARATOR as u32
+        || c == PARAGRAPH_SEPARATOR as u32
+}
+
+/// Returns the set of `\d`.
+pub(crate) fn digits() -> CharSet {
+    CharSet::from_ranges(&[(0x30, 0x39)])
+}
+
+/// Returns the set of `\w`.
+pub(crate) fn word_chars() -> CharSet {
+    CharSet::from_ranges(&[(0x30, 0x39), (0x41, 0x5a), (0x5f, 0x5f), (0x61, 0x7a)])
+}
+
+/// Returns the set of `\s`, which is the white space and the line terminators.
+pub(crate) fn spaces() -> CharSet {
+    CharSet::from_ranges(&[
+        (0x09, 0x0d),
+        (0x20, 0x20),
+        (0xa0, 0xa0),
+        (0x1680, 0x1680),
+        (0x2000, 0x200a),
+        (0x2028, 0x2029),
+        (0x202f, 0x202f),
+        (0x205f, 0x205f),
+        (0x3000, 0x3000),
+        (0xfeff, 0xfeff),
+    ])
+}
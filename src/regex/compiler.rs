@@ -0,0 +1,336 @@
+use super::class::{canonicalize, CharSet};
+use super::parser::{Ast, Node};
+use super::Flags;
+use crate::RegExpError;
+
+/// The maximum number of instructions in a program.
+const MAX_PROGRAM_SIZE: usize = 1 << 20;
+
+/// An instruction of the backtracking matcher. The instructions that read a character move
+/// to the left of the position when `backward` is set, which is used inside of lookbehinds.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Inst {
+    /// Matches the character exactly.
+    Char {
+        c: u32,
+        backward: bool,
+    },
+    /// Matches a character that has the given canonicalized form.
+    CharIgnoreCase {
+        c: u32,
+        backward: bool,
+    },
+    /// Matches any character, or any character but the line terminators without the `s` flag.
+    Any {
+        backward: bool,
+    },
+    /// Matches a character of the set with the given index.
+    Class {
+        set: usize,
+        negated: bool,
+        backward: bool,
+    },
+    Start,
+    End,
+    WordBoundary {
+        negated: bool,
+    },
+    /// Continues at the first target, and at the second one when backtracking.
+    Split(usize, usize),
+    Jmp(usize),
+    /// Stores the position in a capture slot.
+    Save(usize),
+    /// Unsets the capture slots in the range.
+    ClearSlots(usize, usize),
+    /// Stores the position in a register, to detect the iterations that match nothing.
+    SetMark(usize),
+    /// Fails if the position has not moved since the mark in the register.
+    CheckProgress(usize),
+    BackRef {
+        group: usize,
+        backward: bool,
+    },
+    /// Runs the assertion that starts at the next instruction and ends with a `Match`, then
+    /// continues at `end`.
+    Look {
+        behind: bool,
+        negate: bool,
+        end: usize,
+    },
+    Match,
+}
+
+/// A compiled regular expression.
+#[derive(Debug, Clone)]
+pub(crate) struct Program {
+    pub insts: Vec<Inst>,
+    pub sets: Vec<CharSet>,
+    /// Number of the capture slots, which is two for each group including the whole match.
+    pub slots: usize,
+    /// Number of the registers that are used by the loops.
+    pub registers: usize,
+    pub flags: Flags,
+    /// A code unit that every match starts with, used to skip the positions quickly.
+    pub first_unit: Option<u16>,
+}
+
+pub(crate) fn compile(ast: &Ast, flags: Flags) -> Result<Program, RegExpError> {
+    let mut compiler = Compiler {
+        program: Program {
+            insts: Vec::new(),
+            sets: Vec::new(),
+            slots: (ast.group_count + 1) * 2,
+            registers: 0,
+            flags,
+            first_unit: None,
+        },
+        names: &ast.names,
+    };
+    compiler.compile(&ast.node, false)?;
+    compiler.push(Inst::Match)?;
+
+    let mut program = compiler.program;
+    program.first_unit = match program.insts[0] {
+        Inst::Char { c, .. } if c <= 0xffff && !(0xd800..=0xdfff).contains(&c) => Some(c as u16),
+        _ => None,
+    };
+    Ok(program)
+}
+
+struct Compiler<'a> {
+    program: Program,
+    names: &'a [(String, usize)],
+}
+
+impl<'a> Compiler<'a> {
+    fn push(&mut self, inst: Inst) -> Result<usize, RegExpError> {
+        if self.program.insts.len() >= MAX_PROGRAM_SIZE {
+            return Err(RegExpError::TooLarge);
+        }
+        self.program.insts.push(inst);
+        Ok(self.program.insts.len() - 1)
+    }
+
+    /// Fails if `count` more copies of `size` instructions do not fit in a program.
+    fn reserve(&self, size: usize, count: u32) -> Result<(), RegExpError> {
+        match size
+            .checked_mul(count as usize)
+            .and_then(|n| n.checked_add(self.next_pc()))
+        {
+            Some(n) if n <= MAX_PROGRAM_SIZE => Ok(()),
+            _ => Err(RegExpError::TooLarge),
+        }
+    }
+
+    #[inline]
+    fn next_pc(&self) -> usize {
+        self.program.insts.len()
+    }
+
+    fn compile(&mut self, mut node: &Node, backward: bool) -> Result<(), RegExpError> {
+        // A non-capturing group only changes how the pattern is parsed.
+        while let Node::Group(inner, None) = node {
+            node = inner;
+        }
+
+        match node {
+            Node::Group(node, Some(index)) => {
+                // The end of the group is reached first when matching backward.
+                let (first, last) = if backward {
+                    (index * 2 + 1, index * 2)
+                } else {
+                    (index * 2, index * 2 + 1)
+                };
+                self.push(Inst::Save(first))?;
+                self.compile(node, backward)?;
+                self.push(Inst::Save(last))?;
+            }
+            Node::Look {
+                node,
+                behind,
+                negate,
+            } => {
+                let look = self.push(Inst::Look {
+                    behind: *behind,
+                    negate: *negate,
+                    end: 0,
+                })?;
+                self.compile(node, *behind)?;
+                self.push(Inst::Match)?;
+                let next = self.next_pc();
+                if let Inst::Look { end, .. } = &mut self.program.insts[look] {
+                    *end = next;
+                }
+            }
+            Node::Repeat {
+                node,
+                min,
+                max,
+                greedy,
+                groups,
+            } => self.compile_repeat(node, *min, *max, *greedy, *groups, backward)?,
+            Node::Concat(nodes) if backward => {
+                for node in nodes.iter().rev() {
+                    self.compile(node, backward)?;
+                }
+            }
+            Node::Concat(nodes) => {
+                for node in nodes {
+                    self.compile(node, backward)?;
+                }
+            }
+            Node::Alt(nodes) => {
+                let mut jumps = Vec::with_capacity(nodes.len());
+                for (i, node) in nodes.iter().enumerate() {
+                    if i + 1 == nodes.len() {
+                        self.compile(node, backward)?;
+                        break;
+                    }
+                    let split = self.push(Inst::Split(0, 0))?;
+                    self.compile(node, backward)?;
+                    jumps.push(self.push(Inst::Jmp(0))?);
+                    self.program.insts[split] = Inst::Split(split + 1, self.next_pc());
+                }
+                let end = self.next_pc();
+                for jump in jumps {
+                    self.program.insts[jump] = Inst::Jmp(end);
+                }
+            }
+            _ => self.compile_leaf(node, backward)?,
+        }
+        Ok(())
+    }
+
+    /// Compiles the nodes that do not contain other nodes. They are kept out of `compile` so
+    /// its stack frame stays small, since it recurses once per nesting level of the pattern.
+    fn compile_leaf(&mut self, node: &Node, backward: bool) -> Result<(), RegExpError> {
+        let flags = self.program.flags;
+        match node {
+            Node::Empty => {}
+            Node::Char(c) if flags.ignore_case => {
+                let c = canonicalize(*c, flags.unicode);
+                self.push(Inst::CharIgnoreCase { c, backward })?;
+            }
+            Node::Char(c) => {
+                self.push(Inst::Char { c: *c, backward })?;
+            }
+            Node::Any => {
+                self.push(Inst::Any { backward })?;
+            }
+            Node::Class { set, negated } => match set.single() {
+                Some(c) if !negated => self.compile_leaf(&Node::Char(c), backward)?,
+                _ => {
+                    self.program.sets.push(set.clone());
+                    let set = self.program.sets.len() - 1;
+                    self.push(Inst::Class {
+                        set,
+                        negated: *negated,
+                        backward,
+                    })?;
+                }
+            },
+            Node::Start => {
+                self.push(Inst::Start)?;
+            }
+            Node::End => {
+                self.push(Inst::End)?;
+            }
+            Node::WordBoundary { negated } => {
+                self.push(Inst::WordBoundary { negated: *negated })?;
+            }
+            Node::BackRef(group) => {
+                self.push(Inst::BackRef {
+                    group: *group,
+                    backward,
+                })?;
+            }
+            Node::NamedBackRef(name, index) => {
+                let group = self
+                    .names
+                    .iter()
+                    .find(|(other, _)| other == name)
+                    .map(|&(_, group)| group)
+                    .ok_or(RegExpError::InvalidBackReference { index: *index })?;
+                self.push(Inst::BackRef { group, backward })?;
+            }
+            _ => unreachable!("not a leaf node"),
+        }
+        Ok(())
+    }
+
+    fn compile_repeat(
+        &mut self,
+        node: &Node,
+        min: u32,
+        max: Option<u32>,
+        greedy: bool,
+        groups: (usize, usize),
+        backward: bool,
+    ) -> Result<(), RegExpError> {
+        // The captures of the groups inside the atom are reset in every iteration.
+        let clear = if groups.1 > groups.0 {
+            Some(Inst::ClearSlots(groups.0 * 2, groups.1 * 2))
+        } else {
+            None
+        };
+
+        for i in 0..min {
+            let start = self.next_pc();
+            if let Some(clear) = &clear {
+                self.push(clear.clone())?;
+            }
+            self.compile(node, backward)?;
+            if i == 0 {
+                let size = self.next_pc() - start;
+                if size == 0 {
+                    // Every iteration of an atom that compiles to nothing is the same as none.
+                    return Ok(());
+                }
+                // Fails before the iterations are unrolled instead of after millions of them.
+                self.reserve(size, min - 1)?;
+            }
+        }
+
+        let register = self.program.registers;
+        self.program.registers += 1;
+        let optional = max.map(|max| max - min);
+        let mut splits = Vec::new();
+        let mut count = 0;
+        // Without a maximum the loop ends after the first iteration, which jumps back.
+        while optional != Some(count) {
+            let split = self.push(Inst::Split(0, 0))?;
+            splits.push(split);
+            self.push(Inst::SetMark(register))?;
+            let start = self.next_pc();
+            if let Some(clear) = &clear {
+                self.push(clear.clone())?;
+            }
+            self.compile(node, backward)?;
+            if count == 0 && self.next_pc() == start {
+                // An empty optional iteration fails, so it can never be taken.
+                self.program.insts.truncate(split);
+                return Ok(());
+            }
+            // An optional iteration that matches the empty string fails.
+            self.push(Inst::CheckProgress(register))?;
+            if optional.is_none() {
+                self.push(Inst::Jmp(split))?;
+                break;
+            }
+            if let (0, Some(optional)) = (count, optional) {
+                self.reserve(self.next_pc() - split, optional - 1)?;
+            }
+            count += 1;
+        }
+
+        let end = self.next_pc();
+        for split in splits {
+            self.program.insts[split] = if greedy {
+                Inst::Split(split + 1, end)
+            } else {
+                Inst::Split(end, split + 1)
+            };
+        }
+        Ok(())
+    }
+}
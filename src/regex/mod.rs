@@ -0,0 +1,466 @@
+mod class;
+mod compiler;
+mod parser;
+mod vm;
+
+use self::compiler::Program;
+use self::vm::Input;
+use super::{DynamicString, RegExpError};
+use std::fmt;
+use std::ops::Range;
+
+/// The flags of a regular expression.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Flags {
+    /// `g`: all of the matches are replaced by `RegExp::replace`.
+    pub global: bool,
+    /// `i`: the case of the characters is ignored.
+    pub ignore_case: bool,
+    /// `m`: `^` and `$` also match at the line terminators.
+    pub multiline: bool,
+    /// `s`: `.` also matches the line terminators.
+    pub dot_all: bool,
+    /// `u`: the pattern and the text are treated as code points instead of code units, and
+    /// the syntax is strict.
+    pub unicode: bool,
+    /// `y`: matches only start at the position where the search starts.
+    pub sticky: bool,
+}
+
+impl Flags {
+    /// Parses the flags from a string such as `"gi"`.
+    /// ```
+    /// use dynstr::Flags;
+    /// let flags = Flags::parse("gu").unwrap();
+    /// assert!(flags.global && flags.unicode && !flags.sticky);
+    /// assert!(Flags::parse("gg").is_err());
+    /// ```
+    pub fn parse(flags: &str) -> Result<Self, RegExpError> {
+        let mut result = Flags::default();
+        for flag in flags.chars() {
+            let field = match flag {
+                'g' => &mut result.global,
+                'i' => &mut result.ignore_case,
+                'm' => &mut result.multiline,
+                's' => &mut result.dot_all,
+                'u' => &mut result.unicode,
+                'y' => &mut result.sticky,
+                _ => return Err(RegExpError::InvalidFlag { flag }),
+            };
+            if *field {
+                return Err(RegExpError::InvalidFlag { flag });
+            }
+            *field = true;
+        }
+        Ok(result)
+    }
+}
+
+impl fmt::Display for Flags {
+    /// Writes the flags in the same order as `RegExp.prototype.flags` in JavaScript.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let flags = [
+            (self.global, 'g'),
+            (self.ignore_case, 'i'),
+            (self.multiline, 'm'),
+            (self.dot_all, 's'),
+            (self.unicode, 'u'),
+            (self.sticky, 'y'),
+        ];
+        for &(set, flag) in flags.iter() {
+            if set {
+                write!(f, "{}", flag)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A regular expression with the syntax and the semantics of JavaScript, which searches a
+/// DynamicString through its chunks without flattening it. The positions are indices of
+/// UTF-16 code units, like in JavaScript.
+/// ```
+/// use dynstr::{DynamicString, RegExp};
+/// let re = RegExp::new(r"(?<year>\d{4})-(?<month>\d{2})", "").unwrap();
+/// let text = DynamicString::new("Released on ") + "2021-04-01.";
+/// let captures = re.exec(&text).unwrap();
+/// assert_eq!(captures.start(), 12);
+/// assert_eq!(captures.get(0).unwrap(), "2021-04");
+/// assert_eq!(captures.name("month").unwrap(), "04");
+/// ```
+#[derive(Debug, Clone)]
+pub struct RegExp {
+    source: String,
+    program: Program,
+    group_count: usize,
+    /// The names of the named groups and their indices.
+    names: Vec<(String, usize)>,
+}
+
+impl RegExp {
+    /// Compiles the pattern with the flags given as a string, like the `RegExp` constructor.
+    #[inline]
+    pub fn new(pattern: &str, flags: &str) -> Result<Self, RegExpError> {
+        Self::with_flags(pattern, Flags::parse(flags)?)
+    }
+
+    /// Compiles the pattern with the given flags.
+    pub fn with_flags(pattern: &str, flags: Flags) -> Result<Self, RegExpError> {
+        let chars = if flags.unicode {
+            pattern.chars().map(|c| c as u32).collect()
+        } else {
+            pattern.encode_utf16().map(|c| c as u32).collect()
+        };
+        let ast = parser::parse(chars, flags.unicode)?;
+        let program = compiler::compile(&ast, flags)?;
+        Ok(RegExp {
+            source: pattern.to_owned(),
+            program,
+            group_count: ast.group_count,
+            names: ast.names,
+        })
+    }
+
+    /// Returns the pattern of the regular expression.
+    #[inline]
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// Returns the flags of the regular expression.
+    #[inline]
+    pub fn flags(&self) -> Flags {
+        self.program.flags
+    }
+
+    /// Returns the number of the capturing groups, not counting the whole match.
+    #[inline]
+    pub fn group_count(&self) -> usize {
+        self.group_count
+    }
+
+    /// Returns true if the regular expression matches anywhere in the text, or at its start
+    /// with the `y` flag.
+    #[inline]
+    pub fn is_match(&self, text: &DynamicString) -> bool {
+        self.exec(text).is_some()
+    }
+
+    /// Returns the first match in the text and its captures.
+    #[inline]
+    pub fn exec<'a>(&'a self, text: &'a DynamicString) -> Option<Captures<'a>> {
+        self.exec_at(text, 0)
+    }
+
+    /// Returns the first match that starts at or after `last_index`, or only at `last_index`
+    /// with the `y` flag. Like in JavaScript the caller is expected to continue from the end of
+    /// the match when iterating with the `g` or the `y` flag.
+    /// ```
+    /// use dynstr::{DynamicString, RegExp};
+    /// let re = RegExp::new("a", "y").unwrap();
+    /// let text = DynamicString::new("aab");
+    /// assert_eq!(re.exec_at(&text, 1).unwrap().range(0), Some(1..2));
+    /// assert!(re.exec_at(&text, 2).is_none());
+    /// ```
+    pub fn exec_at<'a>(
+        &'a self,
+        text: &'a DynamicString,
+        last_index: usize,
+    ) -> Option<Captures<'a>> {
+        if last_index > text.len() {
+            return None;
+        }
+        let input = Input::new(text);
+        let sticky = self.program.flags.sticky;
+        let slots = vm::search(&self.program, &input, last_index, sticky)?;
+        Some(Captures::new(self, text, slots))
+    }
+
+    /// Returns an iterator over the successive matches in the text, like `matchAll` in
+    /// JavaScript. An empty match is followed by a search from the next character.
+    /// ```
+    /// use dynstr::{DynamicString, RegExp};
+    /// let re = RegExp::new(r"\d+", "g").unwrap();
+    /// let text = DynamicString::new("1, 22 and 333");
+    /// let numbers: Vec<DynamicString> = re.captures_iter(&text).map(|c| c.get(0).unwrap()).collect();
+    /// assert_eq!(numbers, vec!["1", "22", "333"]);
+    /// ```
+    #[inline]
+    pub fn captures_iter<'a>(&'a self, text: &'a DynamicString) -> CapturesIter<'a> {
+        CapturesIter {
+            regexp: self,
+            text,
+            input: Input::new(text),
+            last_index: 0,
+        }
+    }
+
+    /// Replaces the first match, or all of the matches with the `g` flag, like `replace` in
+    /// JavaScript. The replacement can refer to the match with `$&`, to the groups with `$1`
+    /// and `$<name>`, to the text before and after the match with `` $` `` and `$'`, and `$$`
+    /// is a single `$`.
+    /// ```
+    /// use dynstr::{DynamicString, RegExp};
+    /// let re = RegExp::new(r"(\w+)@(\w+)", "g").unwrap();
+    /// let text = DynamicString::new("alice@home, bob@work");
+    /// assert_eq!(re.replace(&text, "$2:$1"), "home:alice, work:bob");
+    /// ```
+    pub fn replace(&self, text: &DynamicString, replacement: &str) -> DynamicString {
+        let template: Vec<u16> = replacement.encode_utf16().collect();
+        let mut result = DynamicString::empty();
+        let mut last_index = 0;
+        let limit = if self.program.flags.global {
+            usize::MAX
+        } else {
+            1
+        };
+
+        for captures in self.captures_iter(text).take(limit) {
+            result = result + text.slice(last_index, captures.start() - last_index);
+            result = captures.expand(&template, result);
+            last_index = captures.end();
+        }
+
+        result + text.slice(last_index, text.len() - last_index)
+    }
+
+    /// Divides the text at the matches, like `split` in JavaScript. The captures of the
+    /// groups are inserted between the substrings, and the groups that did not participate in
+    /// the match are `None`.
+    /// ```
+    /// use dynstr::{DynamicString, RegExp};
+    /// let re = RegExp::new(r"\s*(,)?\s*", "").unwrap();
+    /// let text = DynamicString::new("a , b");
+    /// let parts: Vec<Option<String>> = re
+    ///     .split(&text, None)
+    ///     .into_iter()
+    ///     .map(|part| part.map(String::from))
+    ///     .collect();
+    /// assert_eq!(parts, vec![Some("a".into()), Some(",".into()), Some("b".into())]);
+    /// ```
+    pub fn split(&self, text: &DynamicString, limit: Option<usize>) -> Vec<Option<DynamicString>> {
+        let limit = limit.unwrap_or(usize::MAX);
+        let mut result = Vec::new();
+        if limit == 0 {
+            return result;
+        }
+
+        let input = Input::new(text);
+        let size = text.len();
+        let unicode = self.program.flags.unicode;
+        if size == 0 {
+            if vm::search(&self.program, &input, 0, true).is_none() {
+                result.push(Some(text.clone()));
+            }
+            return result;
+        }
+
+        let mut p = 0;
+        let mut q = 0;
+        while q < size {
+            let slots = match vm::search(&self.program, &input, q, true) {
+                Some(slots) => slots,
+                None => {
+                    q = input.advance(q, unicode);
+                    continue;
+                }
+            };
+            let e = slots[1].unwrap().min(size);
+            if e == p {
+                q = input.advance(q, unicode);
+                continue;
+            }
+
+            result.push(Some(text.slice(p, q - p)));
+            if result.len() == limit {
+                return result;
+            }
+            p = e;
+            for group in 1..=self.group_count {
+                result.push(match (slots[group * 2], slots[group * 2 + 1]) {
+                    (Some(start), Some(end)) => Some(text.slice(start, end - start)),
+                    _ => None,
+                });
+                if result.len() == limit {
+                    return result;
+                }
+            }
+            q = p;
+        }
+
+        result.push(Some(text.slice(p, size - p)));
+        result
+    }
+}
+
+impl fmt::Display for RegExp {
+    /// Writes the regular expression as a JavaScript literal.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let source = if self.source.is_empty() {
+            "(?:)"
+        } else {
+            &self.source
+        };
+        write!(f, "/{}/{}", source, self.program.flags)
+    }
+}
+
+/// A match of a regular expression and the captures of its groups, the group `0` is the
+/// whole match.
+#[derive(Debug, Clone)]
+pub struct Captures<'a> {
+    regexp: &'a RegExp,
+    text: &'a DynamicString,
+    slots: Vec<Option<usize>>,
+}
+
+impl<'a> Captures<'a> {
+    #[inline]
+    fn new(regexp: &'a RegExp, text: &'a DynamicString, slots: Vec<Option<usize>>) -> Self {
+        Captures {
+            regexp,
+            text,
+            slots,
+        }
+    }
+
+    /// Returns the number of the groups, including the whole match.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.slots.len() / 2
+    }
+
+    /// Always returns false, since the whole match is always present.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        false
+    }
+
+    /// Returns the index of the start of the match.
+    #[inline]
+    pub fn start(&self) -> usize {
+        self.slots[0].unwrap()
+    }
+
+    /// Returns the index of the end of the match.
+    #[inline]
+    pub fn end(&self) -> usize {
+        self.slots[1].unwrap()
+    }
+
+    /// Returns the range of the group in the text, or `None` if the group did not
+    /// participate in the match.
+    pub fn range(&self, group: usize) -> Option<Range<usize>> {
+        match (self.slots.get(group * 2)?, self.slots.get(group * 2 + 1)?) {
+            (Some(start), Some(end)) => Some(*start..*end),
+            _ => None,
+        }
+    }
+
+    /// Returns the text of the group, or `None` if the group did not participate in the
+    /// match.
+    #[inline]
+    pub fn get(&self, group: usize) -> Option<DynamicString> {
+        let range = self.range(group)?;
+        Some(self.text.slice(range.start, range.end - range.start))
+    }
+
+    /// Returns the text of the group with the given name.
+    pub fn name(&self, name: &str) -> Option<DynamicString> {
+        let &(_, group) = self.regexp.names.iter().find(|(other, _)| other == name)?;
+        self.get(group)
+    }
+
+    /// Appends the replacement template with the references to the match expanded.
+    fn expand(&self, template: &[u16], mut result: DynamicString) -> DynamicString {
+        const DOLLAR: u16 = b'$' as u16;
+        let group_count = self.regexp.group_count;
+        let digit = |i: usize| match template.get(i) {
+            Some(&c) if (0x30..=0x39).contains(&c) => Some((c - 0x30) as usize),
+            _ => None,
+        };
+
+        let mut literal_start = 0;
+        let mut i = 0;
+        while i < template.len() {
+            if template[i] != DOLLAR || i + 1 == template.len() {
+                i += 1;
+                continue;
+            }
+
+            let text = self.text;
+            let (value, len) = match template[i + 1] {
+                DOLLAR => (Some(DynamicString::SingleOneByteChar(b'$')), 2),
+                0x26 => (self.get(0), 2),
+                0x60 => (Some(text.slice(0, self.start())), 2),
+                0x27 => (Some(text.slice(self.end(), text.len() - self.end())), 2),
+                0x3c if !self.regexp.names.is_empty() => {
+                    match template[i + 2..].iter().position(|&c| c == 0x3e) {
+                        Some(end) => {
+                            let name = String::from_utf16_lossy(&template[i + 2..i + 2 + end]);
+                            (self.name(&name), end + 3)
+                        }
+                        None => {
+                            i += 1;
+                            continue;
+                        }
+                    }
+                }
+                _ => match (digit(i + 1), digit(i + 2)) {
+                    (Some(a), Some(b)) if (1..=group_count).contains(&(a * 10 + b)) => {
+                        (self.get(a * 10 + b), 3)
+                    }
+                    (Some(a), _) if (1..=group_count).contains(&a) => (self.get(a), 2),
+                    _ => {
+                        i += 1;
+                        continue;
+                    }
+                },
+            };
+
+            result = result + DynamicString::from_units(template[literal_start..i].to_vec());
+            if let Some(value) = value {
+                result = result + value;
+            }
+            i += len;
+            literal_start = i;
+        }
+
+        result + DynamicString::from_units(template[literal_start..].to_vec())
+    }
+}
+
+/// An iterator over the successive matches of a regular expression, created by
+/// `RegExp::captures_iter`.
+#[derive(Debug)]
+pub struct CapturesIter<'a> {
+    regexp: &'a RegExp,
+    text: &'a DynamicString,
+    input: Input<'a>,
+    last_index: usize,
+}
+
+impl<'a> Iterator for CapturesIter<'a> {
+    type Item = Captures<'a>;
+
+    fn next(&mut self) -> Option<Captures<'a>> {
+        if self.last_index > self.input.len() {
+            return None;
+        }
+        let program = &self.regexp.program;
+        let slots = vm::search(program, &self.input, self.last_index, program.flags.sticky);
+        let captures = match slots {
+            Some(slots) => Captures::new(self.regexp, self.text, slots),
+            None => {
+                self.last_index = usize::MAX;
+                return None;
+            }
+        };
+        self.last_index = if captures.end() == captures.start() {
+            self.input.advance(captures.end(), program.flags.unicode)
+        } else {
+            captures.end()
+        };
+        Some(captures)
+    }
+}
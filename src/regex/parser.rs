@@ -0,0 +1,680 @@
+use super::class::{digits, spaces, word_chars, CharSet};
+use crate::encoding::{is_lead_surrogate, is_trail_surrogate};
+use crate::RegExpError;
+
+/// The maximum nesting depth of the groups, since the syntax tree is parsed and compiled
+/// recursively.
+const MAX_DEPTH: usize = 256;
+
+/// A node of the syntax tree of a regular expression.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Node {
+    /// Matches the empty string.
+    Empty,
+    /// A character, which is a code point in the unicode mode and a code unit otherwise.
+    Char(u32),
+    /// The `.` character class.
+    Any,
+    /// A character class, the set of `[^...]` is not inverted and `negated` is set instead.
+    Class {
+        set: CharSet,
+        negated: bool,
+    },
+    /// The `^` assertion.
+    Start,
+    /// The `$` assertion.
+    End,
+    /// The `\b` and `\B` assertions.
+    WordBoundary {
+        negated: bool,
+    },
+    /// A group and the index of its captures if it is a capturing group.
+    Group(Box<Node>, Option<usize>),
+    /// A lookahead or lookbehind assertion.
+    Look {
+        node: Box<Node>,
+        behind: bool,
+        negate: bool,
+    },
+    /// A back reference to the group with the given index.
+    BackRef(usize),
+    /// A back reference to the group with the given name, and the index of the escape in the
+    /// pattern. It is resolved once all of the groups are known.
+    NamedBackRef(String, usize),
+    /// A quantified atom, `groups` is the range of the capturing groups inside of it.
+    Repeat {
+        node: Box<Node>,
+        min: u32,
+        max: Option<u32>,
+        greedy: bool,
+        groups: (usize, usize),
+    },
+    Concat(Vec<Node>),
+    Alt(Vec<Node>),
+}
+
+/// The result of parsing a pattern.
+#[derive(Debug)]
+pub(crate) struct Ast {
+    pub node: Node,
+    /// Number of the capturing groups.
+    pub group_count: usize,
+    /// The names of the named groups and their indices.
+    pub names: Vec<(String, usize)>,
+}
+
+/// Parses a pattern, which is given as code points in the unicode mode and as code units
+/// otherwise.
+pub(crate) fn parse(pattern: Vec<u32>, unicode: bool) -> Result<Ast, RegExpError> {
+    let (total_groups, has_names) = scan_groups(&pattern);
+    let mut parser = Parser {
+        chars: pattern,
+        pos: 0,
+        unicode,
+        total_groups,
+        has_names,
+        group_count: 0,
+        names: Vec::new(),
+        depth: 0,
+    };
+    let node = parser.parse_disjunction()?;
+    if let Some(c) = parser.peek() {
+        // Only an unbalanced `)` stops the top level disjunction.
+        return Err(parser.unexpected(c));
+    }
+    Ok(Ast {
+        node,
+        group_count: parser.group_count,
+        names: parser.names,
+    })
+}
+
+/// Counts the capturing groups of the pattern and checks if any of them is named, which is
+/// needed to know the meaning of escapes such as `\2` before the group is parsed.
+fn scan_groups(pattern: &[u32]) -> (usize, bool) {
+    let mut count = 0;
+    let mut has_names = false;
+    let mut in_class = false;
+    let mut i = 0;
+    while i < pattern.len() {
+        match char_of(pattern[i]) {
+            '\\' => i += 1,
+            '[' => in_class = true,
+            ']' => in_class = false,
+            '(' if !in_class => match pattern.get(i + 1).map(|&c| char_of(c)) {
+                Some('?') => {
+                    let next = pattern.get(i + 2).map(|&c| char_of(c));
+                    let after = pattern.get(i + 3).map(|&c| char_of(c));
+                    if next == Some('<') && after != Some('=') && after != Some('!') {
+                        count += 1;
+                        has_names = true;
+                    }
+                }
+                _ => count += 1,
+            },
+            _ => {}
+        }
+        i += 1;
+    }
+    (count, has_names)
+}
+
+#[inline]
+fn char_of(c: u32) -> char {
+    char::from_u32(c).unwrap_or('\u{fffd}')
+}
+
+/// An atom of a character class, which is either a character or a set of characters.
+enum ClassAtom {
+    Char(u32),
+    Set(CharSet),
+}
+
+struct Parser {
+    chars: Vec<u32>,
+    pos: usize,
+    unicode: bool,
+    /// Number of the capturing groups in the whole pattern.
+    total_groups: usize,
+    /// Whether the pattern has any named groups, which makes `\k` a back reference.
+    has_names: bool,
+    /// Number of the capturing groups that are opened so far.
+    group_count: usize,
+    names: Vec<(String, usize)>,
+    /// Number of the disjunctions that are being parsed, which is one more than the nesting
+    /// depth of the groups.
+    depth: usize,
+}
+
+impl Parser {
+    #[inline]
+    fn peek(&self) -> Option<u32> {
+        self.chars.get(self.pos).copied()
+    }
+
+    #[inline]
+    fn peek_char(&self) -> Option<char> {
+        self.peek().map(char_of)
+    }
+
+    #[inline]
+    fn peek_at(&self, offset: usize) -> Option<char> {
+        self.chars.get(self.pos + offset).map(|&c| char_of(c))
+    }
+
+    /// Consumes the next character if it is `c`.
+    #[inline]
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek_char() == Some(c) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn next(&mut self) -> Result<u32, RegExpError> {
+        let c = self.peek().ok_or(RegExpError::UnexpectedEnd)?;
+        self.pos += 1;
+        Ok(c)
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), RegExpError> {
+        match self.peek() {
+            None => Err(RegExpError::UnexpectedEnd),
+            Some(_) if self.eat(c) => Ok(()),
+            Some(other) => Err(self.unexpected(other)),
+        }
+    }
+
+    #[inline]
+    fn unexpected(&self, c: u32) -> RegExpError {
+        RegExpError::UnexpectedChar {
+            index: self.pos,
+            c: char_of(c),
+        }
+    }
+
+    fn parse_disjunction(&mut self) -> Result<Node, RegExpError> {
+        if self.depth > MAX_DEPTH {
+            return Err(RegExpError::TooLarge);
+        }
+        self.depth += 1;
+        let mut alternatives = vec![self.parse_alternative()?];
+        while self.eat('|') {
+            alternatives.push(self.parse_alternative()?);
+        }
+        self.depth -= 1;
+        Ok(if alternatives.len() == 1 {
+            alternatives.pop().unwrap()
+        } else {
+            Node::Alt(alternatives)
+        })
+    }
+
+    fn parse_alternative(&mut self) -> Result<Node, RegExpError> {
+        let mut terms = Vec::new();
+        while let Some(c) = self.peek_char() {
+            if c == '|' || c == ')' {
+                break;
+            }
+            terms.push(self.parse_term()?);
+        }
+        Ok(match terms.len() {
+            0 => Node::Empty,
+            1 => terms.pop().unwrap(),
+            _ => Node::Concat(terms),
+        })
+    }
+
+    fn parse_term(&mut self) -> Result<Node, RegExpError> {
+        let start = self.pos;
+        let groups_before = self.group_count;
+        let atom = match self.peek_char() {
+            Some('^') => {
+                self.pos += 1;
+                return Ok(Node::Start);
+            }
+            Some('$') => {
+                self.pos += 1;
+                return Ok(Node::End);
+            }
+            Some('\\') if matches!(self.peek_at(1), Some('b') | Some('B')) => {
+                let negated = self.peek_at(1) == Some('B');
+                self.pos += 2;
+                return Ok(Node::WordBoundary { negated });
+            }
+            Some('(') if self.peek_at(1) == Some('?') => {
+                let (behind, negate) = match (self.peek_at(2), self.peek_at(3)) {
+                    (Some('='), _) => (false, false),
+                    (Some('!'), _) => (false, true),
+                    (Some('<'), Some('=')) => (true, false),
+                    (Some('<'), Some('!')) => (true, true),
+                    _ => {
+                        let atom = self.parse_atom()?;
+                        return self.parse_quantified(atom, start, groups_before);
+                    }
+                };
+                self.pos += if behind { 4 } else { 3 };
+                let node = self.parse_disjunction()?;
+                self.expect(')')?;
+                let look = Node::Look {
+                    node: Box::new(node),
+                    behind,
+                    negate,
+                };
+                // Lookaheads can be quantified for compatibility with the web, like any atom.
+                if behind || self.unicode {
+                    return Ok(look);
+                }
+                look
+            }
+            _ => self.parse_atom()?,
+        };
+        self.parse_quantified(atom, start, groups_before)
+    }
+
+    /// Parses the quantifier that follows the atom, if there is one.
+    fn parse_quantified(
+        &mut self,
+        atom: Node,
+        start: usize,
+        groups_before: usize,
+    ) -> Result<Node, RegExpError> {
+        let (min, max) = match self.parse_quantifier()? {
+            Some(bounds) => bounds,
+            None => return Ok(atom),
+        };
+        if matches!(max, Some(max) if max < min) {
+            return Err(RegExpError::InvalidQuantifier { index: start });
+        }
+        let greedy = !self.eat('?');
+        Ok(Node::Repeat {
+            node: Box::new(atom),
+            min,
+            max,
+            greedy,
+            groups: (groups_before + 1, self.group_count + 1),
+        })
+    }
+
+    /// Parses a quantifier and returns its bounds, a `{` that does not start a valid
+    /// quantifier is a literal outside of the unicode mode.
+    fn parse_quantifier(&mut self) -> Result<Option<(u32, Option<u32>)>, RegExpError> {
+        let bounds = match self.peek_char() {
+            Some('*') => (0, None),
+            Some('+') => (1, None),
+            Some('?') => (0, Some(1)),
+            Some('{') => {
+                return match self.parse_braces() {
+                    Some(bounds) => Ok(Some(bounds)),
+                    None if self.unicode => Err(RegExpError::InvalidQuantifier { index: self.pos }),
+                    None => Ok(None),
+                };
+            }
+            _ => return Ok(None),
+        };
+        self.pos += 1;
+        Ok(Some(bounds))
+    }
+
+    /// Parses `{n}`, `{n,}` or `{n,m}`, the position is not changed if it is not valid.
+    fn parse_braces(&mut self) -> Option<(u32, Option<u32>)> {
+        let start = self.pos;
+        self.pos += 1;
+        let result = (|| {
+            let min = self.parse_decimal()?;
+            let max = if self.eat(',') {
+                match self.peek_char() {
+                    Some('}') => None,
+                    _ => Some(self.parse_decimal()?),
+                }
+            } else {
+                Some(min)
+            };
+            if self.eat('}') {
+                Some((min, max))
+            } else {
+                None
+            }
+        })();
+        if result.is_none() {
+            self.pos = start;
+        }
+        result
+    }
+
+    /// Parses a decimal number, which saturates instead of overflowing.
+    fn parse_decimal(&mut self) -> Option<u32> {
+        let mut value: Option<u32> = None;
+        while let Some(digit) = self.peek_char().and_then(|c| c.to_digit(10)) {
+            self.pos += 1;
+            value = Some(value.unwrap_or(0).saturating_mul(10).saturating_add(digit));
+        }
+        value
+    }
+
+    fn parse_atom(&mut self) -> Result<Node, RegExpError> {
+        let c = self.peek().ok_or(RegExpError::UnexpectedEnd)?;
+        match char_of(c) {
+            '.' => {
+                self.pos += 1;
+                Ok(Node::Any)
+            }
+            '(' => self.parse_group(),
+            '[' => self.parse_class(),
+            '\\' => self.parse_atom_escape(),
+            '*' | '+' | '?' => Err(RegExpError::NothingToRepeat { index: self.pos }),
+            '{' if self.unicode || self.parse_braces().is_some() => {
+                Err(RegExpError::NothingToRepeat { index: self.pos })
+            }
+            ')' => Err(self.unexpected(c)),
+            ']' | '}' if self.unicode => Err(self.unexpected(c)),
+            _ => {
+                self.pos += 1;
+                Ok(Node::Char(c))
+            }
+        }
+    }
+
+    fn parse_group(&mut self) -> Result<Node, RegExpError> {
+        self.pos += 1;
+        let index = if self.eat('?') {
+            if self.eat(':') {
+                None
+            } else if self.peek_char() == Some('<') {
+                let name_start = self.pos;
+                self.pos += 1;
+                let name = self.parse_group_name()?;
+                if self.names.iter().any(|(other, _)| *other == name) {
+                    return Err(RegExpError::InvalidGroupName { index: name_start });
+                }
+                self.group_count += 1;
+                self.names.push((name, self.group_count));
+                Some(self.group_count)
+            } else {
+                return match self.peek() {
+                    None => Err(RegExpError::UnexpectedEnd),
+                    Some(c) => Err(self.unexpected(c)),
+                };
+            }
+        } else {
+            self.group_count += 1;
+            Some(self.group_count)
+        };
+
+        let node = self.parse_disjunction()?;
+        self.expect(')')?;
+        Ok(Node::Group(Box::new(node), index))
+    }
+
+    /// Parses the name of a group after the `<`, and the closing `>`.
+    fn parse_group_name(&mut self) -> Result<String, RegExpError> {
+        let start = self.pos;
+        let mut name = String::new();
+        loop {
+            let c = self.peek_char().ok_or(RegExpError::UnexpectedEnd)?;
+            if c == '>' {
+                break;
+            }
+            let valid = c == '$'
+                || c == '_'
+                || c.is_alphabetic()
+                || (!name.is_empty()
+                    && (c.is_alphanumeric() || c == '\u{200c}' || c == '\u{200d}'));
+            if !valid {
+                return Err(RegExpError::InvalidGroupName { index: start });
+            }
+            name.push(c);
+            self.pos += 1;
+        }
+        self.pos += 1;
+        if name.is_empty() {
+            return Err(RegExpError::InvalidGroupName { index: start });
+        }
+        Ok(name)
+    }
+
+    fn parse_atom_escape(&mut self) -> Result<Node, RegExpError> {
+        let start = self.pos;
+        self.pos += 1;
+        let c = self.peek_char().ok_or(RegExpError::UnexpectedEnd)?;
+        match c {
+            'd' | 'D' | 'w' | 'W' | 's' | 'S' => {
+                self.pos += 1;
+                Ok(Node::Class {
+                    set: class_escape(c),
+                    negated: false,
+                })
+            }
+            'k' if self.unicode || self.has_names => {
+                self.pos += 1;
+                if !self.eat('<') {
+                    return Err(RegExpError::InvalidEscape { index: start });
+                }
+                let name = self.parse_group_name()?;
+                Ok(Node::NamedBackRef(name, start))
+            }
+            '1'..='9' => {
+                let digits_start = self.pos;
+                let n = self.parse_decimal().unwrap_or(0) as usize;
+                if n <= self.total_groups {
+                    return Ok(Node::BackRef(n));
+                }
+                if self.unicode {
+                    return Err(RegExpError::InvalidBackReference { index: start });
+                }
+                self.pos = digits_start;
+                Ok(Node::Char(self.parse_legacy_escape()))
+            }
+            _ => Ok(Node::Char(self.parse_char_escape(start, false)?)),
+        }
+    }
+
+    /// Parses an escape that stands for a single character, after the `\`.
+    fn parse_char_escape(&mut self, start: usize, in_class: bool) -> Result<u32, RegExpError> {
+        let c = self.next()?;
+        let value = match char_of(c) {
+            't' => 0x09,
+            'n' => 0x0a,
+            'v' => 0x0b,
+            'f' => 0x0c,
+            'r' => 0x0d,
+            'b' if in_class => 0x08,
+            '-' if in_class && self.unicode => 0x2d,
+            '0' if !matches!(self.peek_char(), Some(c) if c.is_ascii_digit()) => 0,
+            '0'..='7' if !self.unicode => {
+                self.pos -= 1;
+                self.parse_legacy_escape()
+            }
+            'c' => match self.peek_char() {
+                Some(letter) if letter.is_ascii_alphabetic() => {
+                    self.pos += 1;
+                    letter as u32 % 32
+                }
+                Some(other)
+                    if in_class && !self.unicode && (other.is_ascii_digit() || other == '_') =>
+                {
+                    self.pos += 1;
+                    other as u32 % 32
+                }
+                _ if self.unicode => return Err(RegExpError::InvalidEscape { index: start }),
+                // `\c` is a backslash followed by `c` for compatibility with the web.
+                _ => {
+                    self.pos -= 1;
+                    0x5c
+                }
+            },
+            'x' => match self.parse_hex(2) {
+                Some(value) => value,
+                None if self.unicode => return Err(RegExpError::InvalidEscape { index: start }),
+                None => 0x78,
+            },
+            'u' => match self.parse_unicode_escape()? {
+                Some(value) => value,
+                None if self.unicode => return Err(RegExpError::InvalidEscape { index: start }),
+                None => 0x75,
+            },
+            '^' | '$' | '\\' | '.' | '*' | '+' | '?' | '(' | ')' | '[' | ']' | '{' | '}' | '|'
+            | '/' => c,
+            _ if self.unicode => return Err(RegExpError::InvalidEscape { index: start }),
+            _ => c,
+        };
+        Ok(value)
+    }
+
+    /// Parses a legacy octal escape of up to three digits, or the digit itself if it is
+    /// `8` or `9`.
+    fn parse_legacy_escape(&mut self) -> u32 {
+        let first = self.peek_char().and_then(|c| c.to_digit(8));
+        let mut value = match first {
+            Some(digit) => digit,
+            None => {
+                self.pos += 1;
+                return self.chars[self.pos - 1];
+            }
+        };
+        self.pos += 1;
+        let max_len = if value <= 3 { 3 } else { 2 };
+        for _ in 1..max_len {
+            match self.peek_char().and_then(|c| c.to_digit(8)) {
+                Some(digit) => {
+                    value = value * 8 + digit;
+                    self.pos += 1;
+                }
+                None => break,
+            }
+        }
+        value
+    }
+
+    /// Parses the given number of hexadecimal digits, the position is not changed if there
+    /// are not enough of them.
+    fn parse_hex(&mut self, len: usize) -> Option<u32> {
+        let mut value = 0;
+        for i in 0..len {
+            value = value * 16 + self.peek_at(i)?.to_digit(16)?;
+        }
+        self.pos += len;
+        Some(value)
+    }
+
+    /// Parses the rest of a `\u` escape, a surrogate pair of escapes is combined and braces
+    /// are allowed in the unicode mode.
+    fn parse_unicode_escape(&mut self) -> Result<Option<u32>, RegExpError> {
+        if self.unicode && self.peek_char() == Some('{') {
+            let start = self.pos;
+            self.pos += 1;
+            let mut value: u32 = 0;
+            let mut len = 0;
+            while let Some(digit) = self.peek_char().and_then(|c| c.to_digit(16)) {
+                value = value.saturating_mul(16).saturating_add(digit);
+                len += 1;
+                self.pos += 1;
+            }
+            if len == 0 || value > 0x10ffff || !self.eat('}') {
+                self.pos = start;
+                return Ok(None);
+            }
+            return Ok(Some(value));
+        }
+
+        let lead = match self.parse_hex(4) {
+            Some(value) => value,
+            None => return Ok(None),
+        };
+        if self.unicode
+            && is_lead_surrogate(lead as u16)
+            && self.peek_char() == Some('\\')
+            && self.peek_at(1) == Some('u')
+        {
+            let start = self.pos;
+            self.pos += 2;
+            match self.parse_hex(4) {
+                Some(trail) if is_trail_surrogate(trail as u16) => {
+                    return Ok(Some(0x10000 + ((lead - 0xd800) << 10) + (trail - 0xdc00)));
+                }
+                _ => self.pos = start,
+            }
+        }
+        Ok(Some(lead))
+    }
+
+    fn parse_class(&mut self) -> Result<Node, RegExpError> {
+        self.pos += 1;
+        let negated = self.eat('^');
+        let mut set = CharSet::new();
+        loop {
+            match self.peek_char() {
+                None => return Err(RegExpError::UnexpectedEnd),
+                Some(']') => {
+                    self.pos += 1;
+                    break;
+                }
+                _ => {}
+            }
+
+            let start = self.pos;
+            let first = self.parse_class_atom()?;
+            if self.peek_char() != Some('-') || matches!(self.peek_at(1), Some(']') | None) {
+                add_atom(&mut set, first);
+                continue;
+            }
+            self.pos += 1;
+            let second = self.parse_class_atom()?;
+            match (first, second) {
+                (ClassAtom::Char(a), ClassAtom::Char(b)) => {
+                    if a > b {
+                        return Err(RegExpError::InvalidClassRange { index: start });
+                    }
+                    set.add_range(a, b);
+                }
+                _ if self.unicode => return Err(RegExpError::InvalidClassRange { index: start }),
+                // The `-` is a literal when one of the ends is a class, like in `[\d-z]`.
+                (first, second) => {
+                    add_atom(&mut set, first);
+                    add_atom(&mut set, second);
+                    set.add(0x2d);
+                }
+            }
+        }
+        Ok(Node::Class { set, negated })
+    }
+
+    fn parse_class_atom(&mut self) -> Result<ClassAtom, RegExpError> {
+        let c = self.next()?;
+        if char_of(c) != '\\' {
+            return Ok(ClassAtom::Char(c));
+        }
+
+        let start = self.pos - 1;
+        match self.peek_char().ok_or(RegExpError::UnexpectedEnd)? {
+            c @ ('d' | 'D' | 'w' | 'W' | 's' | 'S') => {
+                self.pos += 1;
+                Ok(ClassAtom::Set(class_escape(c)))
+            }
+            '1'..='9' if self.unicode => Err(RegExpError::InvalidEscape { index: start }),
+            '1'..='9' => Ok(ClassAtom::Char(self.parse_legacy_escape())),
+            _ => Ok(ClassAtom::Char(self.parse_char_escape(start, true)?)),
+        }
+    }
+}
+
+fn add_atom(set: &mut CharSet, atom: ClassAtom) {
+    match atom {
+        ClassAtom::Char(c) => set.add(c),
+        ClassAtom::Set(other) => set.extend(&other),
+    }
+}
+
+/// Returns the set of a character class escape such as `\d` or `\S`.
+fn class_escape(c: char) -> CharSet {
+    match c {
+        'd' => digits(),
+        'D' => digits().complement(),
+        'w' => word_chars(),
+        'W' => word_chars().complement(),
+        's' => spaces(),
+        _ => spaces().complement(),
+    }
+}
@@ -0,0 +1,402 @@
+use super::class::{canonicalize, is_line_terminator};
+use super::compiler::{Inst, Program};
+use crate::encoding::{is_lead_surrogate, is_trail_surrogate};
+use crate::{ChunkSlice, DynamicString};
+use std::cell::Cell;
+
+/// Random access to the code units of a DynamicString through its chunks, the matcher mostly
+/// moves by one unit so the chunk of the last access is checked first.
+#[derive(Debug)]
+pub(crate) struct Input<'a> {
+    chunks: Vec<ChunkSlice<'a>>,
+    /// The offset of the first unit of each chunk.
+    starts: Vec<usize>,
+    len: usize,
+    /// The index of the chunk that was accessed last.
+    cursor: Cell<usize>,
+}
+
+impl<'a> Input<'a> {
+    pub fn new(text: &'a DynamicString) -> Self {
+        let mut chunks = Vec::new();
+        let mut starts = Vec::new();
+        let mut len = 0;
+        for chunk in text.chunks() {
+            starts.push(len);
+            len += chunk.len();
+            chunks.push(chunk);
+        }
+        Input {
+            chunks,
+            starts,
+            len,
+            cursor: Cell::new(0),
+        }
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns the unit at the index, which must be less than the length.
+    #[inline]
+    pub fn get(&self, index: usize) -> u16 {
+        let mut k = self.cursor.get();
+        let start = self.starts[k];
+        if index < start || index >= start + self.chunks[k].len() {
+            k = self.starts.partition_point(|&start| start <= index) - 1;
+            self.cursor.set(k);
+        }
+        match self.chunks[k] {
+            ChunkSlice::OneByte(s) => s[index - self.starts[k]] as u16,
+            ChunkSlice::TwoByte(s) => s[index - self.starts[k]],
+        }
+    }
+
+    /// Returns the position of the next code unit, or the next code point in the unicode
+    /// mode.
+    #[inline]
+    pub fn advance(&self, index: usize, unicode: bool) -> usize {
+        if unicode
+            && index + 1 < self.len
+            && is_lead_surrogate(self.get(index))
+            && is_trail_surrogate(self.get(index + 1))
+        {
+            index + 2
+        } else {
+            index + 1
+        }
+    }
+
+    /// Reads the character after the position, or before it when `backward` is set, and
+    /// returns it with the position on its other side. Surrogate pairs are read as a single
+    /// character in the unicode mode.
+    #[inline]
+    fn read(&self, pos: usize, backward: bool, unicode: bool) -> Option<(u32, usize)> {
+        if backward {
+            if pos == 0 {
+                return None;
+            }
+            let c = self.get(pos - 1);
+            if unicode && is_trail_surrogate(c) && pos >= 2 {
+                let lead = self.get(pos - 2);
+                if is_lead_surrogate(lead) {
+                    return Some((combine(lead, c), pos - 2));
+                }
+            }
+            Some((c as u32, pos - 1))
+        } else {
+            if pos >= self.len {
+                return None;
+            }
+            let c = self.get(pos);
+            if unicode && is_lead_surrogate(c) && pos + 1 < self.len {
+                let trail = self.get(pos + 1);
+                if is_trail_surrogate(trail) {
+                    return Some((combine(c, trail), pos + 2));
+                }
+            }
+            Some((c as u32, pos + 1))
+        }
+    }
+}
+
+#[inline]
+fn combine(lead: u16, trail: u16) -> u32 {
+    0x10000 + (((lead as u32) - 0xd800) << 10) + ((trail as u32) - 0xdc00)
+}
+
+/// An entry of the backtracking stack.
+#[derive(Debug)]
+enum Backtrack {
+    /// Continue at the instruction and position.
+    Branch { pc: usize, pos: usize },
+    /// Restore the value of a capture slot.
+    Slot { slot: usize, old: Option<usize> },
+    /// Restore the value of a register.
+    Register { register: usize, old: usize },
+}
+
+/// Searches for a match that starts at or after `start`, or only at `start` if `sticky` is
+/// set. Returns the capture slots of the match.
+pub(crate) fn search(
+    program: &Program,
+    input: &Input,
+    start: usize,
+    sticky: bool,
+) -> Option<Vec<Option<usize>>> {
+    let unicode = program.flags.unicode;
+    let mut slots = vec![None; program.slots];
+    let mut registers = vec![0; program.registers];
+    let mut pos = start;
+    while pos <= input.len() {
+        if let (Some(unit), false) = (program.first_unit, sticky) {
+            while pos < input.len() && input.get(pos) != unit {
+                pos += 1;
+            }
+            if pos == input.len() {
+                return None;
+            }
+        }
+
+        let mut vm = Vm {
+            program,
+            input,
+            slots: &mut slots,
+            registers: &mut registers,
+        };
+        if let Some(end) = vm.run(0, pos) {
+            slots[0] = Some(pos);
+            slots[1] = Some(end);
+            return Some(slots);
+        }
+        if sticky {
+            return None;
+        }
+        pos = input.advance(pos, unicode);
+    }
+    None
+}
+
+struct Vm<'p, 'i, 's> {
+    program: &'p Program,
+    input: &'i Input<'i>,
+    slots: &'s mut Vec<Option<usize>>,
+    registers: &'s mut Vec<usize>,
+}
+
+impl<'p, 'i, 's> Vm<'p, 'i, 's> {
+    /// Runs the program from the instruction and position until a `Match` is reached, and
+    /// returns the position of the end of the match. The slots and the registers are
+    /// restored when there is no match.
+    fn run(&mut self, pc: usize, pos: usize) -> Option<usize> {
+        let flags = self.program.flags;
+        let input = self.input;
+        let mut stack: Vec<Backtrack> = Vec::new();
+        let (mut pc, mut pos) = (pc, pos);
+
+        loop {
+            let ok = match self.program.insts[pc] {
+                Inst::Match => return Some(pos),
+                Inst::Char { c, backward } => match input.read(pos, backward, flags.unicode) {
+                    Some((ch, next)) if ch == c => {
+                        pos = next;
+                        true
+                    }
+                    _ => false,
+                },
+                Inst::CharIgnoreCase { c, backward } => {
+                    match input.read(pos, backward, flags.unicode) {
+                        Some((ch, next)) if canonicalize(ch, flags.unicode) == c => {
+                            pos = next;
+                            true
+                        }
+                        _ => false,
+                    }
+                }
+                Inst::Any { backward } => match input.read(pos, backward, flags.unicode) {
+                    Some((ch, next)) if flags.dot_all || !is_line_terminator(ch) => {
+                        pos = next;
+                        true
+                    }
+                    _ => false,
+                },
+                Inst::Class {
+                    set,
+                    negated,
+                    backward,
+                } => match input.read(pos, backward, flags.unicode) {
+                    Some((ch, next)) => {
+                        let set = &self.program.sets[set];
+                        let found = if flags.ignore_case {
+                            set.contains_ignore_case(canonicalize(ch, flags.unicode), flags.unicode)
+                        } else {
+                            set.contains(ch)
+                        };
+                        if found != negated {
+                            pos = next;
+                            true
+                        } else {
+                            false
+                        }
+                    }
+                    None => false,
+                },
+                Inst::Start => {
+                    pos == 0 || (flags.multiline && is_line_terminator(input.get(pos - 1) as u32))
+                }
+                Inst::End => {
+                    pos == input.len()
+                        || (flags.multiline && is_line_terminator(input.get(pos) as u32))
+                }
+                Inst::WordBoundary { negated } => {
+                    let before = pos > 0 && self.is_word(input.get(pos - 1));
+                    let after = pos < input.len() && self.is_word(input.get(pos));
+                    (before != after) != negated
+                }
+                Inst::Split(first, second) => {
+                    stack.push(Backtrack::Branch { pc: second, pos });
+                    pc = first;
+                    continue;
+                }
+                Inst::Jmp(target) => {
+                    pc = target;
+                    continue;
+                }
+                Inst::Save(slot) => {
+                    let old = self.slots[slot];
+                    stack.push(Backtrack::Slot { slot, old });
+                    self.slots[slot] = Some(pos);
+                    true
+                }
+                Inst::ClearSlots(from, to) => {
+                    for slot in from..to {
+                        if let Some(old) = self.slots[slot].take() {
+                            stack.push(Backtrack::Slot {
+                                slot,
+                                old: Some(old),
+                            });
+                        }
+                    }
+                    true
+                }
+                Inst::SetMark(register) => {
+                    let old = self.registers[register];
+                    stack.push(Backtrack::Register { register, old });
+                    self.registers[register] = pos;
+                    true
+                }
+                Inst::CheckProgress(register) => self.registers[register] != pos,
+                Inst::BackRef { group, backward } => {
+                    match self.back_reference(group, pos, backward) {
+                        Some(next) => {
+                            pos = next;
+                            true
+                        }
+                        None => false,
+                    }
+                }
+                Inst::Look {
+                    behind: _,
+                    negate,
+                    end,
+                } => {
+                    // The assertion runs on copies, since its changes are not undone when it
+                    // succeeds.
+                    let mut slots = self.slots.clone();
+                    let mut registers = self.registers.clone();
+                    let mut vm = Vm {
+                        program: self.program,
+                        input,
+                        slots: &mut slots,
+                        registers: &mut registers,
+                    };
+                    // The assertion is atomic, it is never backtracked into.
+                    let found = vm.run(pc + 1, pos).is_some();
+                    if found && !negate {
+                        for (slot, value) in slots.into_iter().enumerate() {
+                            if self.slots[slot] != value {
+                                let old = self.slots[slot];
+                                stack.push(Backtrack::Slot { slot, old });
+                                self.slots[slot] = value;
+                            }
+                        }
+                    }
+                    if found != negate {
+                        pc = end;
+                        continue;
+                    }
+                    false
+                }
+            };
+
+            if ok {
+                pc += 1;
+                continue;
+            }
+
+            // Undo the changes until the last branch, and continue from there.
+            loop {
+                match stack.pop() {
+                    None => return None,
+                    Some(Backtrack::Branch {
+                        pc: next_pc,
+                        pos: next_pos,
+                    }) => {
+                        pc = next_pc;
+                        pos = next_pos;
+                        break;
+                    }
+                    Some(Backtrack::Slot { slot, old }) => self.slots[slot] = old,
+                    Some(Backtrack::Register { register, old }) => self.registers[register] = old,
+                }
+            }
+        }
+    }
+
+    /// Returns true if the unit is a word character for `\b`.
+    #[inline]
+    fn is_word(&self, c: u16) -> bool {
+        let flags = self.program.flags;
+        matches!(c, 0x30..=0x39 | 0x41..=0x5a | 0x5f | 0x61..=0x7a)
+            // These are word characters because they fold to `s` and `k`.
+            || (flags.ignore_case && flags.unicode && (c == 0x17f || c == 0x212a))
+    }
+
+    /// Matches the text of the group at the position, and returns the position on the other
+    /// side of it. A group that did not participate in the match matches the empty string.
+    fn back_reference(&self, group: usize, pos: usize, backward: bool) -> Option<usize> {
+        let (start, end) = match (self.slots[group * 2], self.slots[group * 2 + 1]) {
+            (Some(start), Some(end)) if start <= end => (start, end),
+            _ => return Some(pos),
+        };
+        let len = end - start;
+        let from = if backward {
+            pos.checked_sub(len)?
+        } else if pos + len <= self.input.len() {
+            pos
+        } else {
+            return None;
+        };
+
+        let flags = self.program.flags;
+        let equal = if flags.ignore_case && flags.unicode {
+            self.equal_code_points(start, from, len)
+        } else {
+            (0..len).all(|i| {
+                let a = self.input.get(start + i);
+                let b = self.input.get(from + i);
+                a == b
+                    || (flags.ignore_case
+                        && canonicalize(a as u32, false) == canonicalize(b as u32, false))
+            })
+        };
+        match (equal, backward) {
+            (false, _) => None,
+            (true, true) => Some(from),
+            (true, false) => Some(pos + len),
+        }
+    }
+
+    /// Compares the `len` units at `a` and `b` by their canonicalized code points, since a
+    /// surrogate pair is canonicalized as a whole in the unicode mode.
+    fn equal_code_points(&self, mut a: usize, mut b: usize, len: usize) -> bool {
+        let (end_a, end_b) = (a + len, b + len);
+        // A pair that is split by the end of the range is read as a lone surrogate.
+        let read = |pos: usize, end: usize| match self.input.read(pos, false, true) {
+            Some((c, next)) if next <= end => (c, next),
+            _ => (self.input.get(pos) as u32, pos + 1),
+        };
+        while a < end_a {
+            let (c, next_a) = read(a, end_a);
+            let (d, next_b) = read(b, end_b);
+            if next_a - a != next_b - b || canonicalize(c, true) != canonicalize(d, true) {
+                return false;
+            }
+            a = next_a;
+            b = next_b;
+        }
+        true
+    }
+}
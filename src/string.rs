@@ -1,4 +1,5 @@
-use super::DynamicStringIterator;
+use super::cons::{self, ConsNode};
+use super::{DynamicStringChunks, DynamicStringIterator};
 use std::cmp;
 use std::fmt;
 use std::hash::{Hash, Hasher};
@@ -24,11 +25,8 @@ pub enum DynamicString {
         start: usize,
         length: usize,
     },
-    /// The result of concatenating two DynamicStrings.
-    ConsString {
-        first: Box<DynamicString>,
-        second: Box<DynamicString>,
-    },
+    /// The result of concatenating two DynamicStrings, created by `DynamicString::cons`.
+    ConsString(Arc<ConsNode>),
 }
 
 impl DynamicString {
@@ -41,9 +39,56 @@ impl DynamicString {
                 DynamicString::SingleTwoByteChar(v[0])
             }
             (_, true) => DynamicString::SeqOneByteString(Arc::new(data.as_bytes().to_vec())),
-            (_, false) => {
-                DynamicString::SeqTwoByteString(Arc::new(data.encode_utf16().into_iter().collect()))
+            (_, false) => DynamicString::SeqTwoByteString(Arc::new(data.encode_utf16().collect())),
+        }
+    }
+
+    /// Creates a flat string from a vector of UTF-16 code units, the one-byte representation
+    /// is used when all of the units fit in a single byte.
+    pub(crate) fn from_units(units: Vec<u16>) -> Self {
+        let one_byte = units.iter().all(|&c| c <= 0xff);
+        match (units.len(), one_byte) {
+            (0, _) => DynamicString::empty(),
+            (1, true) => DynamicString::SingleOneByteChar(units[0] as u8),
+            (1, false) => DynamicString::SingleTwoByteChar(units[0]),
+            (_, true) => {
+                DynamicString::SeqOneByteString(Arc::new(units.iter().map(|&c| c as u8).collect()))
+            }
+            (_, false) => DynamicString::SeqTwoByteString(Arc::new(units)),
+        }
+    }
+
+    /// Creates a ConsString from the given strings, unlike `append` the result is never
+    /// flattened. The tree is rebalanced so its depth stays logarithmic in the number of
+    /// leaves, even when the strings are appended one by one.
+    #[inline]
+    pub fn cons(first: DynamicString, second: DynamicString) -> Self {
+        cons::join(first, second)
+    }
+
+    /// Concatenates the given strings pair by pair, which needs fewer rotations than adding
+    /// them one by one.
+    pub(crate) fn concat_balanced(mut parts: Vec<DynamicString>) -> Self {
+        while parts.len() > 1 {
+            let mut iter = parts.into_iter();
+            let mut pairs = Vec::with_capacity(iter.len() / 2 + 1);
+            while let Some(first) = iter.next() {
+                pairs.push(match iter.next() {
+                    Some(second) => DynamicString::cons(first, second),
+                    None => first,
+                });
             }
+            parts = pairs;
+        }
+        parts.pop().unwrap_or(DynamicString::Empty)
+    }
+
+    /// Returns the two parts of the string if it is a concatenation.
+    #[inline]
+    pub(crate) fn as_cons(&self) -> Option<(&DynamicString, &DynamicString)> {
+        match self {
+            DynamicString::ConsString(node) => Some((node.first(), node.second())),
+            _ => None,
         }
     }
 
@@ -62,10 +107,16 @@ impl DynamicString {
             DynamicString::SeqOneByteString(v) => v.len(),
             DynamicString::SeqTwoByteString(v) => v.len(),
             DynamicString::SlicedString { length, .. } => *length,
-            DynamicString::ConsString { first, second } => first.len() + second.len(),
+            DynamicString::ConsString(node) => node.len(),
         }
     }
 
+    /// Returns true if the string has a length of zero.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
     /// Returns true if this string only contains one-byte characters.
     pub fn has_one_byte_char(&self) -> bool {
         match self {
@@ -74,9 +125,7 @@ impl DynamicString {
             | DynamicString::SeqOneByteString(_) => true,
             DynamicString::SingleTwoByteChar(_) | DynamicString::SeqTwoByteString(_) => false,
             DynamicString::SlicedString { root, .. } => root.has_one_byte_char(),
-            DynamicString::ConsString { first, second } => {
-                first.has_one_byte_char() && second.has_one_byte_char()
-            }
+            DynamicString::ConsString(node) => node.has_one_byte_char(),
         }
     }
 
@@ -118,6 +167,27 @@ impl DynamicString {
     pub fn iter(&self) -> DynamicStringIterator {
         self.clone().into_iter()
     }
+
+    /// Returns an iterator over the contiguous pieces of memory that make up this string, in
+    /// order.
+    /// ```
+    /// use dynstr::{ChunkSlice, DynamicString};
+    /// let str = DynamicString::new("Hello world, ") + DynamicString::new("سلام دنیا");
+    /// let chunks: Vec<ChunkSlice> = str.chunks().collect();
+    /// assert_eq!(chunks.len(), 2);
+    /// assert_eq!(chunks[0], ChunkSlice::OneByte(b"Hello world, "));
+    /// ```
+    #[inline]
+    pub fn chunks(&self) -> DynamicStringChunks<'_> {
+        DynamicStringChunks::new(self, false)
+    }
+
+    /// Returns an iterator over the contiguous pieces of memory that make up this string, from
+    /// the last one to the first one.
+    #[inline]
+    pub fn rchunks(&self) -> DynamicStringChunks<'_> {
+        DynamicStringChunks::new(self, true)
+    }
 }
 
 impl From<DynamicString> for String {
@@ -136,10 +206,10 @@ impl From<&DynamicString> for String {
     }
 }
 
-impl Into<DynamicString> for &str {
+impl From<&str> for DynamicString {
     #[inline]
-    fn into(self) -> DynamicString {
-        DynamicString::new(self)
+    fn from(str: &str) -> Self {
+        DynamicString::new(str)
     }
 }
 
@@ -174,14 +244,14 @@ impl PartialEq<DynamicString> for DynamicString {
 impl PartialEq<str> for DynamicString {
     #[inline]
     fn eq(&self, other: &str) -> bool {
-        self.len() == other.len() && self.iter().eq(other.encode_utf16())
+        self.iter().eq(other.encode_utf16())
     }
 }
 
 impl PartialEq<&str> for DynamicString {
     #[inline]
     fn eq(&self, other: &&str) -> bool {
-        self.len() == other.len() && self.iter().eq(other.encode_utf16())
+        self.iter().eq(other.encode_utf16())
     }
 }
 
@@ -195,7 +265,7 @@ impl cmp::Ord for DynamicString {
 impl cmp::PartialOrd for DynamicString {
     #[inline]
     fn partial_cmp(&self, other: &Self) -> Option<cmp::Ordering> {
-        self.iter().partial_cmp(other.iter())
+        Some(self.cmp(other))
     }
 }
 
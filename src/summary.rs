@@ -0,0 +1,413 @@
+use super::encoding::{is_lead_surrogate, is_trail_surrogate};
+use super::line::{CR, LF, LINE_SEPARATOR, PARAGRAPH_SEPARATOR};
+use super::utf8::utf8_width;
+use super::{ChunkSlice, DynamicString};
+use std::cmp;
+
+/// Metrics of a string, computed for every ConsString when it is created so they can be used
+/// to descend the tree.
+///
+/// The summary of a string does not depend on the characters before it, the effect of the
+/// previous character (such as a surrogate pair or a `\r\n` which is split between the two
+/// parts of a ConsString) is applied when two summaries are concatenated.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Summary {
+    len: usize,
+    lines: usize,
+    code_points: usize,
+    utf8_len: usize,
+    /// The number of surrogates that are not part of a pair.
+    lone_surrogates: usize,
+    /// The first and the last characters, only valid when the length is not zero.
+    first: u16,
+    last: u16,
+}
+
+impl Summary {
+    /// Computes the summary of a sequence of characters.
+    pub fn from_units<I: IntoIterator<Item = u16>>(units: I) -> Self {
+        let mut summary = Summary::default();
+        let mut prev = None;
+        for c in units {
+            summary.len += 1;
+            summary.lines += LinesMetric::weight(prev, c);
+            summary.code_points += CodePointMetric::weight(prev, c);
+            summary.utf8_len += Utf8Metric::weight(prev, c);
+            if is_trail_surrogate(c) && matches!(prev, Some(p) if is_lead_surrogate(p)) {
+                // The lead surrogate was counted as unpaired.
+                summary.lone_surrogates -= 1;
+            } else if is_lead_surrogate(c) || is_trail_surrogate(c) {
+                summary.lone_surrogates += 1;
+            }
+            if prev.is_none() {
+                summary.first = c;
+            }
+            prev = Some(c);
+        }
+        summary.last = prev.unwrap_or(0);
+        summary
+    }
+
+    /// Returns the number of UTF-16 code units.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns true if the string is empty.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the number of line terminators, the number of lines is one more than this.
+    #[inline]
+    pub fn lines(&self) -> usize {
+        self.lines
+    }
+
+    /// Returns the number of code points, where unpaired surrogates count as one.
+    #[inline]
+    pub fn code_points(&self) -> usize {
+        self.code_points
+    }
+
+    /// Returns the number of bytes in the UTF-8 representation.
+    #[inline]
+    pub fn utf8_len(&self) -> usize {
+        self.utf8_len
+    }
+
+    /// Returns true if the string does not contain any unpaired surrogates.
+    #[inline]
+    pub(crate) fn is_well_formed(&self) -> bool {
+        self.lone_surrogates == 0
+    }
+
+    /// Returns the summary of the concatenation of the two strings.
+    pub fn concat(&self, other: &Summary) -> Summary {
+        if self.len == 0 {
+            return *other;
+        }
+        if other.len == 0 {
+            return *self;
+        }
+
+        let prev = self.last;
+        let c = other.first;
+        // A lead surrogate at the end and a trail surrogate at the start are now a pair.
+        let paired = if is_lead_surrogate(prev) && is_trail_surrogate(c) {
+            2
+        } else {
+            0
+        };
+        Summary {
+            len: self.len + other.len,
+            lines: self.lines + adjust::<LinesMetric>(other.lines, prev, c),
+            code_points: self.code_points + adjust::<CodePointMetric>(other.code_points, prev, c),
+            utf8_len: self.utf8_len + adjust::<Utf8Metric>(other.utf8_len, prev, c),
+            lone_surrogates: self.lone_surrogates + other.lone_surrogates - paired,
+            first: self.first,
+            last: other.last,
+        }
+    }
+}
+
+/// Replaces the weight that `c` was given as the first character of a string with its weight
+/// after `prev`.
+#[inline(always)]
+fn adjust<M: Metric>(measure: usize, prev: u16, c: u16) -> usize {
+    measure - M::weight(None, c) + M::weight(Some(prev), c)
+}
+
+/// A metric is a way of measuring a string, which can be used to descend the tree of a
+/// DynamicString without visiting the nodes that are skipped.
+pub trait Metric {
+    /// Returns the measure of a string with the given summary.
+    fn measure(summary: &Summary) -> usize;
+
+    /// Returns how much the character `c` adds to the measure, `prev` is the character before
+    /// it or `None` at the start of the string.
+    fn weight(prev: Option<u16>, c: u16) -> usize;
+}
+
+/// Measures a string by its UTF-16 code units.
+#[derive(Debug, Clone, Copy)]
+pub struct Utf16Metric;
+
+/// Measures a string by its line terminators (`\n`, `\r\n`, `\r`, U+2028 and U+2029).
+#[derive(Debug, Clone, Copy)]
+pub struct LinesMetric;
+
+/// Measures a string by its code points.
+#[derive(Debug, Clone, Copy)]
+pub struct CodePointMetric;
+
+/// Measures a string by the length of its UTF-8 representation.
+#[derive(Debug, Clone, Copy)]
+pub struct Utf8Metric;
+
+impl Metric for Utf16Metric {
+    #[inline]
+    fn measure(summary: &Summary) -> usize {
+        summary.len
+    }
+
+    #[inline]
+    fn weight(_: Option<u16>, _: u16) -> usize {
+        1
+    }
+}
+
+impl Metric for LinesMetric {
+    #[inline]
+    fn measure(summary: &Summary) -> usize {
+        summary.lines
+    }
+
+    #[inline]
+    fn weight(prev: Option<u16>, c: u16) -> usize {
+        match (prev, c) {
+            // The line break of a `\r\n` is counted on the `\r`.
+            (Some(CR), LF) => 0,
+            (_, LF) | (_, CR) | (_, LINE_SEPARATOR) | (_, PARAGRAPH_SEPARATOR) => 1,
+            _ => 0,
+        }
+    }
+}
+
+impl Metric for CodePointMetric {
+    #[inline]
+    fn measure(summary: &Summary) -> usize {
+        summary.code_points
+    }
+
+    #[inline]
+    fn weight(prev: Option<u16>, c: u16) -> usize {
+        match prev {
+            Some(p) if is_lead_surrogate(p) && is_trail_surrogate(c) => 0,
+            _ => 1,
+        }
+    }
+}
+
+impl Metric for Utf8Metric {
+    #[inline]
+    fn measure(summary: &Summary) -> usize {
+        summary.utf8_len
+    }
+
+    #[inline]
+    fn weight(prev: Option<u16>, c: u16) -> usize {
+        utf8_width(prev, c)
+    }
+}
+
+impl DynamicString {
+    /// Returns the summary of this string. The summary of a ConsString is computed when it is
+    /// created, for the other strings the characters are scanned.
+    /// ```
+    /// use dynstr::DynamicString;
+    /// let summary = (DynamicString::new("a😴\r") + "\nb").summary();
+    /// assert_eq!(summary.len(), 6);
+    /// assert_eq!(summary.lines(), 1);
+    /// assert_eq!(summary.code_points(), 5);
+    /// assert_eq!(summary.utf8_len(), 8);
+    /// ```
+    pub fn summary(&self) -> Summary {
+        match self {
+            DynamicString::ConsString(node) => node.summary(),
+            _ => summary_range(self, 0, self.len()),
+        }
+    }
+
+    /// Returns the measure of the first `offset` characters of this string, the offset is
+    /// clamped to the length of the string.
+    /// ```
+    /// use dynstr::{DynamicString, LinesMetric, Utf8Metric};
+    /// let str = DynamicString::new("line 1\nline 2\n") + "😴 line 3";
+    /// assert_eq!(str.measure::<LinesMetric>(10), 1);
+    /// assert_eq!(str.measure::<Utf8Metric>(16), 18);
+    /// ```
+    pub fn measure<M: Metric>(&self, offset: usize) -> usize {
+        M::measure(&summary_range(self, 0, offset))
+    }
+
+    /// Returns the smallest offset where the measure of the string before it is at least
+    /// `measure`, or `None` if the measure of the whole string is less than that.
+    /// ```
+    /// use dynstr::{DynamicString, LinesMetric};
+    /// let str = DynamicString::new("line 1\nline 2\n") + "line 3";
+    /// // The start of the third line.
+    /// assert_eq!(str.offset_of::<LinesMetric>(2), Some(14));
+    /// assert_eq!(str.offset_of::<LinesMetric>(3), None);
+    /// ```
+    pub fn offset_of<M: Metric>(&self, measure: usize) -> Option<usize> {
+        offset_of::<M>(self, measure)
+    }
+
+    /// Returns the zero-based line and column (in UTF-16 code units) of the given offset, or
+    /// `None` if the offset is past the end of the string. This gives the same result as
+    /// `LineIndex::line_col` without building an index.
+    /// ```
+    /// use dynstr::DynamicString;
+    /// let str = DynamicString::new("let a;\r\n") + "let b;";
+    /// assert_eq!(str.line_col(12), Some((1, 4)));
+    /// ```
+    pub fn line_col(&self, offset: usize) -> Option<(usize, usize)> {
+        if offset > self.len() {
+            return None;
+        }
+
+        let mut line = self.measure::<LinesMetric>(offset);
+        // An offset between `\r` and `\n` belongs to the line before the terminator.
+        if offset > 0
+            && self.char_code_at(offset - 1) == Some(CR)
+            && self.char_code_at(offset) == Some(LF)
+        {
+            line -= 1;
+        }
+
+        let start = self.line_start(line)?;
+        Some((line, offset - start))
+    }
+
+    /// Returns the offset of the first character of the given zero-based line, or `None` if
+    /// the string does not have that many lines.
+    pub fn line_start(&self, line: usize) -> Option<usize> {
+        let offset = self.offset_of::<LinesMetric>(line)?;
+        if offset > 0
+            && self.char_code_at(offset - 1) == Some(CR)
+            && self.char_code_at(offset) == Some(LF)
+        {
+            Some(offset + 1)
+        } else {
+            Some(offset)
+        }
+    }
+}
+
+/// Computes the summary of the characters of `node` in the given range.
+pub(crate) fn summary_range(node: &DynamicString, start: usize, end: usize) -> Summary {
+    let mut summary = Summary::default();
+    // The parts of the range that are left, the next one is at the top.
+    let mut stack = vec![(node, start, end)];
+    while let Some((node, start, end)) = stack.pop() {
+        let end = cmp::min(end, node.len());
+        if start >= end {
+            continue;
+        }
+
+        let part = match node {
+            DynamicString::SlicedString {
+                root,
+                start: offset,
+                ..
+            } => {
+                stack.push((root, offset + start, offset + end));
+                continue;
+            }
+            DynamicString::ConsString(cons) if start == 0 && end == cons.len() => cons.summary(),
+            DynamicString::ConsString(_) => {
+                push_parts(&mut stack, node, start, end);
+                continue;
+            }
+            _ => match leaf_chunk(node, start, end) {
+                ChunkSlice::OneByte(v) => Summary::from_units(v.iter().map(|&c| c as u16)),
+                ChunkSlice::TwoByte(v) => Summary::from_units(v.iter().copied()),
+            },
+        };
+        summary = summary.concat(&part);
+    }
+    summary
+}
+
+/// Finds the smallest offset of `node` where the measure of the characters before it reaches
+/// `target`, or `None` if the measure of the whole string is less than that.
+fn offset_of<M: Metric>(node: &DynamicString, target: usize) -> Option<usize> {
+    if target == 0 {
+        return Some(0);
+    }
+
+    // The measure and the length of the characters that were visited, and the last one.
+    let mut measure = 0;
+    let mut offset = 0;
+    let mut prev = None;
+    let mut stack = vec![(node, 0, node.len())];
+    while let Some((node, start, end)) = stack.pop() {
+        let end = cmp::min(end, node.len());
+        if start >= end {
+            continue;
+        }
+
+        let chunk = match node {
+            DynamicString::SlicedString {
+                root,
+                start: offset,
+                ..
+            } => {
+                stack.push((root, offset + start, offset + end));
+                continue;
+            }
+            DynamicString::ConsString(cons) => {
+                if start == 0 && end == cons.len() {
+                    let summary = cons.summary();
+                    let node_measure = match prev {
+                        Some(p) => adjust::<M>(M::measure(&summary), p, summary.first),
+                        None => M::measure(&summary),
+                    };
+                    // Skip the whole node without visiting it if the target is not in it.
+                    if measure + node_measure < target {
+                        measure += node_measure;
+                        offset += summary.len;
+                        prev = Some(summary.last);
+                        continue;
+                    }
+                }
+                push_parts(&mut stack, node, start, end);
+                continue;
+            }
+            _ => leaf_chunk(node, start, end),
+        };
+
+        for i in 0..chunk.len() {
+            let c = chunk.get(i).expect("the index is in the chunk");
+            measure += M::weight(prev, c);
+            offset += 1;
+            if measure >= target {
+                return Some(offset);
+            }
+            prev = Some(c);
+        }
+    }
+    None
+}
+
+/// Pushes the parts of a concatenation that overlap with the range to the stack, so that the
+/// first part is visited first.
+fn push_parts<'a>(
+    stack: &mut Vec<(&'a DynamicString, usize, usize)>,
+    node: &'a DynamicString,
+    start: usize,
+    end: usize,
+) {
+    let (first, second) = node.as_cons().expect("a concatenation has two parts");
+    let first_len = first.len();
+    if end > first_len {
+        stack.push((second, start.saturating_sub(first_len), end - first_len));
+    }
+    if start < first_len {
+        stack.push((first, start, end));
+    }
+}
+
+/// Returns the characters of a flat string in the given range.
+fn leaf_chunk(node: &DynamicString, start: usize, end: usize) -> ChunkSlice<'_> {
+    match node {
+        DynamicString::SingleOneByteChar(c) => ChunkSlice::OneByte(std::slice::from_ref(c)),
+        DynamicString::SingleTwoByteChar(c) => ChunkSlice::TwoByte(std::slice::from_ref(c)),
+        DynamicString::SeqOneByteString(v) => ChunkSlice::OneByte(&v[start..end]),
+        DynamicString::SeqTwoByteString(v) => ChunkSlice::TwoByte(&v[start..end]),
+        _ => ChunkSlice::OneByte(&[]),
+    }
+}
@@ -0,0 +1,95 @@
+use super::encoding::{is_lead_surrogate, is_trail_surrogate};
+use super::offsets::OffsetIndex;
+use super::{DynamicString, IndexedString, Metric, Utf8Metric};
+
+/// The Utf8Index converts between UTF-8 byte offsets and UTF-16 offsets of a DynamicString in
+/// logarithmic time, unpaired surrogates take three bytes like in WTF-8.
+/// ```
+/// use dynstr::{DynamicString, Utf8Index};
+/// let index = Utf8Index::new(DynamicString::new("aé😴b"));
+/// assert_eq!(index.utf8_len(), 8);
+/// assert_eq!(index.utf8_offset(4), Some(7));
+/// assert_eq!(index.utf16_offset(7), Some(4));
+/// ```
+#[derive(Debug, Clone)]
+pub struct Utf8Index {
+    string: IndexedString,
+    offsets: OffsetIndex,
+}
+
+impl Utf8Index {
+    /// Creates a new Utf8Index from a DynamicString.
+    pub fn new(string: DynamicString) -> Self {
+        Utf8Index::from(IndexedString::new(string))
+    }
+
+    /// Returns the number of bytes in the UTF-8 representation of the string.
+    #[inline]
+    pub fn utf8_len(&self) -> usize {
+        self.offsets.total()
+    }
+
+    /// Returns the number of UTF-16 code units in the string.
+    #[inline]
+    pub fn utf16_len(&self) -> usize {
+        self.string.len()
+    }
+
+    /// Returns the underlying IndexedString.
+    #[inline]
+    pub fn indexed(&self) -> &IndexedString {
+        &self.string
+    }
+
+    /// Converts a UTF-16 offset to a UTF-8 byte offset, an offset in the middle of a
+    /// surrogate pair is moved to the start of the pair.
+    #[inline]
+    pub fn utf8_offset(&self, unit: usize) -> Option<usize> {
+        self.offsets.to_offset(&self.string, unit)
+    }
+
+    /// Converts a UTF-8 byte offset to a UTF-16 offset, an offset in the middle of a
+    /// multi-byte sequence is moved to the start of the sequence.
+    #[inline]
+    pub fn utf16_offset(&self, byte: usize) -> Option<usize> {
+        self.offsets.to_unit(&self.string, byte)
+    }
+}
+
+impl From<IndexedString> for Utf8Index {
+    fn from(string: IndexedString) -> Self {
+        let offsets = OffsetIndex::new(&string, Utf8Metric::weight);
+        Utf8Index { string, offsets }
+    }
+}
+
+impl From<DynamicString> for Utf8Index {
+    fn from(string: DynamicString) -> Self {
+        Utf8Index::new(string)
+    }
+}
+
+impl DynamicString {
+    /// Returns the number of bytes in the UTF-8 representation of this string, unpaired
+    /// surrogates take three bytes like in WTF-8 (or the replacement character).
+    /// ```
+    /// use dynstr::DynamicString;
+    /// assert_eq!(DynamicString::new("aé😴b").utf8_len(), 8);
+    /// ```
+    #[inline]
+    pub fn utf8_len(&self) -> usize {
+        self.summary().utf8_len()
+    }
+}
+
+/// Returns the number of UTF-8 bytes that the character adds to the string, a surrogate pair
+/// takes four bytes which are divided as three for the first half and one for the second.
+#[inline(always)]
+pub(crate) fn utf8_width(prev: Option<u16>, c: u16) -> usize {
+    match c {
+        0..=0x7f => 1,
+        0x80..=0x7ff => 2,
+        _ if is_trail_surrogate(c) && matches!(prev, Some(p) if is_lead_surrogate(p)) => 1,
+        _ => 3,
+    }
+}
@@ -0,0 +1,127 @@
+use dynstr::{CaseSensitivity, DynamicString, MatchMode, PatternFinder};
+use std::cmp::Ordering;
+
+fn eq(a: &str, b: &str) -> bool {
+    DynamicString::new(a).eq_ignore_case(&DynamicString::new(b))
+}
+
+#[test]
+fn simple_folding() {
+    assert!(eq("Hello", "hELLO"));
+    assert!(eq("ΣΑΣ", "σας"));
+    assert!(eq("ſ", "S"));
+    assert!(eq("\u{212a}elvin", "kelvin"));
+    assert!(eq("ÀÉÎÕÜ", "àéîõü"));
+    assert!(eq("STRAẞE", "straße"));
+    // Full case folding would be needed for these.
+    assert!(!eq("ß", "SS"));
+    assert!(!eq("İ", "i"));
+    assert!(!eq("ı", "I"));
+    assert!(!eq("😴", "😴a"));
+    assert!(eq("😴", "😴"));
+
+    let a = DynamicString::new("ÀB");
+    let b = DynamicString::new("àb");
+    assert!(!a.eq_ignore_ascii_case(&b));
+    assert!(a.eq_with_case(&DynamicString::new("Àb"), CaseSensitivity::IgnoreAsciiCase));
+    assert!(!a.eq_with_case(&b, CaseSensitivity::Sensitive));
+}
+
+#[test]
+fn folding_table() {
+    // The representative of the class comes from CaseFolding.txt, the Cherokee letters fold
+    // to upper case.
+    assert_eq!(CaseSensitivity::IgnoreCase.fold(0xab70), 0x13a0);
+    assert_eq!(CaseSensitivity::IgnoreCase.fold(0x13a0), 0x13a0);
+    assert!(eq("\u{13a0}\u{13f8}", "\u{ab70}\u{13f0}"));
+    assert!(eq("\u{3f4}", "θ"));
+    assert!(eq("ϑ", "Θ"));
+    assert!(eq("\u{1e9e}", "ß"));
+    assert!(!eq("\u{1e9e}", "ss"));
+    // Characters without an entry fold to themselves.
+    assert_eq!(CaseSensitivity::IgnoreCase.fold(0x2603), 0x2603);
+    assert_eq!(CaseSensitivity::IgnoreCase.fold(0xd83d), 0xd83d);
+}
+
+#[test]
+fn compare() {
+    let cmp = |a: &str, b: &str| DynamicString::new(a).cmp_ignore_case(&DynamicString::new(b));
+    assert_eq!(cmp("ABC", "abc"), Ordering::Equal);
+    assert_eq!(cmp("abc", "ABD"), Ordering::Less);
+    assert_eq!(cmp("Z", "a"), Ordering::Greater);
+    assert_eq!(cmp("ab", "ABC"), Ordering::Less);
+    assert_eq!(cmp("Σ", "ς"), Ordering::Equal);
+}
+
+/// Returns the matches of the pattern by comparing every window of the text.
+fn naive(text: &DynamicString, pattern: &DynamicString, mode: MatchMode) -> Vec<usize> {
+    let mut result: Vec<usize> = Vec::new();
+    for i in 0..=text.len() - pattern.len() {
+        if !text.slice(i, pattern.len()).eq_ignore_case(pattern) {
+            continue;
+        }
+        match (mode, result.last()) {
+            (MatchMode::NonOverlapping, Some(&last)) if i < last + pattern.len() => {}
+            _ => result.push(i),
+        }
+    }
+    result
+}
+
+#[test]
+fn search_across_chunks() {
+    let mut text = DynamicString::empty();
+    for i in 0..60 {
+        let piece = ["Straße ", "ΣΊΣ", "υφος ", "STRAẞE", "straße."][i % 5];
+        text = DynamicString::cons(text, DynamicString::new(piece));
+    }
+    assert_eq!(text.index_of_ignore_case("strasse"), None);
+
+    for &mode in [MatchMode::Overlapping, MatchMode::NonOverlapping].iter() {
+        for pattern in ["STRAßE", "σίσυφοσ", "e.s", "ss", "Σ"].iter() {
+            let ptn = DynamicString::new(pattern);
+            let found: Vec<usize> =
+                PatternFinder::with_case(&text, &ptn, mode, CaseSensitivity::IgnoreCase).collect();
+            assert_eq!(found, naive(&text, &ptn, mode), "{}", pattern);
+            assert_eq!(found.is_empty(), *pattern == "ss", "{}", pattern);
+        }
+    }
+}
+
+#[test]
+fn index_of_ignore_case() {
+    let str = DynamicString::new("Hello WORLD, hello world");
+    assert_eq!(str.index_of_ignore_case("world"), Some(6));
+    assert_eq!(str.index_of_ignore_case(""), Some(0));
+    assert_eq!(
+        str.index_of_ignore_case("HELLO WORLD, HELLO WORLD"),
+        Some(0)
+    );
+    assert_eq!(str.index_of_ignore_case("HELLO WORLD, HELLO WORLDS"), None);
+    assert!(str.starts_with_ignore_case("HELLO"));
+    assert!(!str.starts_with_ignore_case("world"));
+
+    let found: Vec<usize> = PatternFinder::with_case(
+        &str,
+        &DynamicString::new("HELLO"),
+        MatchMode::Overlapping,
+        CaseSensitivity::IgnoreAsciiCase,
+    )
+    .collect();
+    assert_eq!(found, vec![0, 13]);
+}
+
+#[test]
+fn ignore_case_patterns() {
+    let str = DynamicString::new("Σίσυφος 𝐀b");
+    assert_eq!(str.index_of_ignore_case('Σ'), Some(0));
+    assert_eq!(str.index_of_ignore_case('ς'), Some(0));
+    assert_eq!(str.index_of_ignore_case(&['Φ', 'Υ'][..]), Some(3));
+    assert_eq!(str.index_of_ignore_case('B'), Some(10));
+    assert_eq!(str.index_of_ignore_case('𝐀'), Some(8));
+    assert_eq!(str.index_of_ignore_case(|c: u16| c == 0x3c3), Some(2));
+    assert!(str.starts_with_ignore_case('σ'));
+    assert!(str.starts_with_ignore_case(&['x', 'ς'][..]));
+    assert!(str.starts_with_ignore_case(DynamicString::new("ΣΊΣ")));
+    assert!(!str.starts_with_ignore_case(|c: u16| c == 0x3c3));
+}
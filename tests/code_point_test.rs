@@ -0,0 +1,63 @@
+use dynstr::{CodePointIndex, DynamicString};
+
+#[test]
+fn basic() {
+    let index = CodePointIndex::new(DynamicString::new("Hello 😴 World"));
+    assert_eq!(index.len(), 13);
+    assert_eq!(index.code_point_at(0), Some('H' as u32));
+    assert_eq!(index.code_point_at(6), Some(0x1f634));
+    assert_eq!(index.code_point_at(7), Some(' ' as u32));
+    assert_eq!(index.code_point_at(12), Some('d' as u32));
+    assert_eq!(index.code_point_at(13), None);
+    assert_eq!(index.slice_code_points(6..9), "😴 W");
+    assert_eq!(index.slice_code_points(10..), "rld");
+
+    let empty = CodePointIndex::new(DynamicString::empty());
+    assert!(empty.is_empty());
+    assert_eq!(empty.code_point_at(0), None);
+    assert_eq!(empty.unit_offset(0), Some(0));
+}
+
+#[test]
+fn offsets() {
+    // Long enough to span multiple blocks, with pairs split between the chunks.
+    let emoji = DynamicString::new(&"😴a😄".repeat(50));
+    let str = DynamicString::cons(
+        DynamicString::new("0123456789abcdef"),
+        DynamicString::cons(emoji.slice(0, 101), emoji.slice(101, 199)),
+    );
+    let index = CodePointIndex::new(str.clone());
+    let expected: Vec<u32> = String::from(str.clone())
+        .chars()
+        .map(|c| c as u32)
+        .collect();
+    assert_eq!(index.len(), expected.len());
+
+    let mut unit = 0;
+    for (n, c) in expected.iter().enumerate() {
+        assert_eq!(index.code_point_at(n), Some(*c));
+        assert_eq!(index.unit_offset(n), Some(unit));
+        assert_eq!(index.code_point_offset(unit), Some(n));
+        if *c > 0xffff {
+            // The middle of a pair belongs to the same code point.
+            assert_eq!(index.code_point_offset(unit + 1), Some(n));
+            unit += 2;
+        } else {
+            unit += 1;
+        }
+    }
+    assert_eq!(index.unit_offset(expected.len()), Some(str.len()));
+    assert_eq!(index.code_point_offset(str.len()), Some(expected.len()));
+    assert_eq!(index.unit_offset(expected.len() + 1), None);
+    assert_eq!(index.code_point_offset(str.len() + 1), None);
+}
+
+#[test]
+fn lone_surrogates() {
+    let str = DynamicString::from_code_points(vec![0xdc00, 0x61, 0xd800, 0xd83d, 0xde34]).unwrap();
+    let index = CodePointIndex::new(str);
+    assert_eq!(index.len(), 4);
+    assert_eq!(index.code_point_at(0), Some(0xdc00));
+    assert_eq!(index.code_point_at(2), Some(0xd800));
+    assert_eq!(index.code_point_at(3), Some(0x1f634));
+}
@@ -0,0 +1,186 @@
+use dynstr::{ChunkSlice, DynamicString, DynamicStringError};
+
+#[test]
+fn from_utf16() {
+    let units: Vec<u16> = "Hello 😴 World".encode_utf16().collect();
+    let str = DynamicString::from_utf16(&units).unwrap();
+    assert_eq!(str, "Hello 😴 World");
+    assert!(!str.has_one_byte_char());
+
+    assert_eq!(
+        DynamicString::from_utf16(&[]).unwrap(),
+        DynamicString::empty()
+    );
+    assert!(DynamicString::from_utf16(&[0x41, 0x42])
+        .unwrap()
+        .has_one_byte_char());
+    assert_eq!(
+        DynamicString::from_utf16(&[0x41, 0xdc00, 0x42]),
+        Err(DynamicStringError::LoneSurrogate { index: 1 })
+    );
+    assert_eq!(
+        DynamicString::from_utf16(&[0xd83d, 0xde34, 0xd83d]),
+        Err(DynamicStringError::LoneSurrogate { index: 2 })
+    );
+    assert_eq!(
+        DynamicString::from_utf16_lossy(&[0x41, 0xdc00]),
+        "A\u{fffd}"
+    );
+}
+
+#[test]
+fn from_code_points() {
+    let str = DynamicString::from_code_points(vec![0x1f634, 0x41]).unwrap();
+    assert_eq!(str, "😴A");
+    assert_eq!(str.len(), 3);
+
+    let lone = DynamicString::from_code_points(vec![0xd800]).unwrap();
+    assert_eq!(lone.iter().collect::<Vec<u16>>(), vec![0xd800]);
+}
+
+#[test]
+fn try_to_string() {
+    let str = DynamicString::new("😴😄😃⛔🎠🚓🚇") + DynamicString::new("0123456789");
+    assert_eq!(str.try_to_string().unwrap(), "😴😄😃⛔🎠🚓🚇0123456789");
+    assert_eq!(
+        str.slice(1, 20).try_to_string(),
+        Err(DynamicStringError::LoneSurrogate { index: 0 })
+    );
+    assert_eq!(
+        str.slice(0, 5).try_to_string(),
+        Err(DynamicStringError::LoneSurrogate { index: 4 })
+    );
+}
+
+#[test]
+fn eq_str_non_ascii() {
+    // The UTF-16 length differs from the UTF-8 length of the same text.
+    assert_eq!(DynamicString::new("héllo"), "héllo");
+    assert_eq!(DynamicString::new("😴 سلام"), "😴 سلام");
+    assert!(DynamicString::new("é") == *"é");
+    assert_ne!(DynamicString::new("é"), "e");
+}
+
+#[test]
+fn wtf8() {
+    let str = DynamicString::new("Hello 😴 ⛔");
+    assert_eq!(str.to_wtf8(), "Hello 😴 ⛔".as_bytes());
+
+    let broken = DynamicString::from_code_points(vec![0x61, 0xdc00, 0xd800, 0x62]).unwrap();
+    let bytes = broken.to_wtf8();
+    assert_eq!(DynamicString::from_wtf8(&bytes).unwrap(), broken);
+    assert_eq!(&String::from(broken), "a\u{fffd}\u{fffd}b");
+}
+
+#[test]
+fn invalid_wtf8() {
+    // Truncated sequence.
+    assert_eq!(
+        DynamicString::from_wtf8(&[0x61, 0xe2, 0x9b]),
+        Err(DynamicStringError::InvalidWtf8 { index: 1 })
+    );
+    // Overlong encoding.
+    assert_eq!(
+        DynamicString::from_wtf8(&[0xc0, 0x80]),
+        Err(DynamicStringError::InvalidWtf8 { index: 0 })
+    );
+    // Surrogate pair encoded as two three-byte sequences.
+    assert_eq!(
+        DynamicString::from_wtf8(&[0xed, 0xa0, 0xbd, 0xed, 0xb8, 0xb4]),
+        Err(DynamicStringError::InvalidWtf8 { index: 3 })
+    );
+}
+
+#[test]
+fn is_well_formed() {
+    let emoji = DynamicString::new("😴😄😃⛔🎠🚓🚇");
+    let str = DynamicString::cons(emoji.clone(), DynamicString::new("0123456789"));
+    assert!(str.is_well_formed());
+    assert!(!str.slice(1, 20).is_well_formed());
+    assert!(DynamicString::new("Hello").is_well_formed());
+    assert!(DynamicString::empty().is_well_formed());
+
+    let lead = DynamicString::from_code_points(vec![0xd83d]).unwrap();
+    let trail = DynamicString::from_code_points(vec![0xde34]).unwrap();
+    assert!(!lead.is_well_formed());
+    // A pair split between the two parts of a ConsString is well-formed.
+    let pair = DynamicString::cons(lead.clone(), trail.clone());
+    assert!(pair.is_well_formed());
+    assert!(!DynamicString::cons(trail.clone(), lead.clone()).is_well_formed());
+    assert!(pair.to_well_formed().is_well_formed());
+    let two_byte = DynamicString::from_code_points(vec![0x26d4, 0xd83d]).unwrap();
+    assert!(!two_byte.is_well_formed());
+    assert!(DynamicString::cons(two_byte, trail).is_well_formed());
+}
+
+#[test]
+fn to_well_formed() {
+    let lead = DynamicString::from_code_points(vec![0xd83d]).unwrap();
+    let trail = DynamicString::from_code_points(vec![0xde34]).unwrap();
+    let text = DynamicString::new("0123456789abcdef");
+
+    let str = DynamicString::cons(
+        DynamicString::cons(text.clone(), lead.clone()),
+        DynamicString::cons(
+            trail.clone(),
+            DynamicString::cons(trail.clone(), text.clone()),
+        ),
+    );
+    let fixed = str.to_well_formed();
+    assert!(fixed.is_well_formed());
+    assert_eq!(fixed, "0123456789abcdef😴\u{fffd}0123456789abcdef");
+
+    let fixed = DynamicString::cons(lead.clone(), text.clone()).to_well_formed();
+    assert_eq!(fixed, "\u{fffd}0123456789abcdef");
+    // The well-formed part must be shared with the original string.
+    assert_eq!(shared_chunks(&fixed, &text), 1);
+
+    // Only the pair that is split between the two parts is kept, the rest of each part is
+    // still shared.
+    let str = DynamicString::cons(
+        DynamicString::cons(trail.clone(), DynamicString::cons(text.clone(), lead)),
+        DynamicString::cons(trail, text.clone()),
+    );
+    let fixed = str.to_well_formed();
+    assert!(fixed.is_well_formed());
+    assert_eq!(fixed, "\u{fffd}0123456789abcdef😴0123456789abcdef");
+    assert_eq!(shared_chunks(&fixed, &text), 2);
+}
+
+#[test]
+fn well_formed_deep_rope() {
+    // A rope of many appends, the check reads the summaries and the repair only copies the
+    // leaf with the unpaired surrogate.
+    let text = DynamicString::new("0123456789abcdef");
+    let mut str = DynamicString::empty();
+    for _ in 0..20_000 {
+        str = DynamicString::cons(str, DynamicString::new("x😴"));
+    }
+    str = DynamicString::cons(str, text.clone());
+    assert!(str.is_well_formed());
+    assert_eq!(str.to_well_formed(), str);
+
+    // Leaves the lead surrogate of the last pair in the middle of the tree.
+    let broken = DynamicString::cons(str.slice(0, 59_999), text.clone());
+    assert!(!broken.is_well_formed());
+    let fixed = broken.to_well_formed();
+    assert!(fixed.is_well_formed());
+    assert_eq!(fixed.len(), broken.len());
+    assert_eq!(fixed.char_code_at(59_998), Some(0xfffd));
+    assert_eq!(fixed.char_code_at(59_996), Some(0xde34));
+    assert_eq!(shared_chunks(&fixed, &text), 1);
+
+    let units: Vec<u16> = broken.iter().collect();
+    assert_eq!(fixed, DynamicString::from_utf16_lossy(&units));
+}
+
+/// Returns the number of chunks of `str` that point to the buffer of the one-byte `text`.
+fn shared_chunks(str: &DynamicString, text: &DynamicString) -> usize {
+    let buffer = match text {
+        DynamicString::SeqOneByteString(v) => v.as_ptr(),
+        _ => panic!("expected a SeqOneByteString"),
+    };
+    str.chunks()
+        .filter(|chunk| matches!(chunk, ChunkSlice::OneByte(s) if s.as_ptr() == buffer))
+        .count()
+}
@@ -1,4 +1,4 @@
-use dynstr::{DynamicString, IndexedString};
+use dynstr::{ChunkSlice, DynamicString, IndexedString};
 
 #[test]
 fn basic() {
@@ -11,10 +11,7 @@ fn basic() {
 
 #[test]
 fn cons() {
-    let str = DynamicString::ConsString {
-        first: Box::new(DynamicString::new("012345")),
-        second: Box::new(DynamicString::new("6789")),
-    };
+    let str = DynamicString::cons(DynamicString::new("012345"), DynamicString::new("6789"));
     let indexed = IndexedString::new(str);
     assert_eq!(indexed.at(2), '2' as u16);
     assert_eq!(indexed.at(5), '5' as u16);
@@ -57,13 +54,10 @@ fn slice_cons() {
         length: 5,
     };
     // 01234-01234
-    let str = DynamicString::ConsString {
-        first: Box::new(sliced.clone()),
-        second: Box::new(DynamicString::ConsString {
-            first: Box::new(DynamicString::new("-")),
-            second: Box::new(sliced.clone()),
-        }),
-    };
+    let str = DynamicString::cons(
+        sliced.clone(),
+        DynamicString::cons(DynamicString::new("-"), sliced.clone()),
+    );
     let indexed = IndexedString::new(str);
     assert_eq!(indexed.at(0), '0' as u16);
     assert_eq!(indexed.at(4), '4' as u16);
@@ -81,13 +75,10 @@ fn slice_cons_slice() {
         start: 2,
         length: 5,
     };
-    let root = DynamicString::ConsString {
-        first: Box::new(sliced.clone()),
-        second: Box::new(DynamicString::ConsString {
-            first: Box::new(DynamicString::new("-")),
-            second: Box::new(sliced.clone()),
-        }),
-    };
+    let root = DynamicString::cons(
+        sliced.clone(),
+        DynamicString::cons(DynamicString::new("-"), sliced.clone()),
+    );
     let str = DynamicString::SlicedString {
         root: Box::new(root),
         start: 4,
@@ -99,3 +90,152 @@ fn slice_cons_slice() {
     assert_eq!(indexed.at(2), '0' as u16);
     assert_eq!(indexed.len(), 3);
 }
+
+#[test]
+fn get() {
+    let str = DynamicString::cons(DynamicString::new("012345"), DynamicString::new("6789"));
+    let indexed = IndexedString::new(str);
+    assert_eq!(indexed.get(0), Some('0' as u16));
+    assert_eq!(indexed.get(9), Some('9' as u16));
+    assert_eq!(indexed.get(10), None);
+}
+
+#[test]
+fn append() {
+    let sliced = DynamicString::SlicedString {
+        root: Box::new(DynamicString::new("aa0123456789")),
+        start: 2,
+        length: 5,
+    };
+    let mut indexed = IndexedString::new(DynamicString::new("x"));
+    for _ in 0..3 {
+        indexed.append(sliced.clone());
+        indexed.append("-");
+    }
+    assert_eq!(indexed.len(), 19);
+    assert_eq!(indexed.at(0), 'x' as u16);
+    assert_eq!(indexed.at(1), '0' as u16);
+    assert_eq!(indexed.at(6), '-' as u16);
+    assert_eq!(indexed.at(11), '4' as u16);
+    assert_eq!(indexed.at(17), '4' as u16);
+    assert_eq!(indexed.get(19), None);
+    assert_eq!(indexed.into_dynamic(), "x01234-01234-01234-");
+
+    let mut indexed = IndexedString::new(DynamicString::empty());
+    indexed.append(DynamicString::empty());
+    assert!(indexed.is_empty());
+    indexed.append("ab");
+    assert_eq!(indexed.at(1), 'b' as u16);
+}
+
+#[test]
+fn access_pattern() {
+    let piece = DynamicString::new("0123456789abcdefghij");
+    let mut str = DynamicString::empty();
+    for i in 0..10 {
+        str = str + piece.slice(i, 17) + "-";
+    }
+    let expected: Vec<u16> = str.iter().collect();
+    let indexed = IndexedString::new(str);
+
+    // Forward, backward and jumping accesses must all agree with the iterator.
+    for (i, c) in expected.iter().enumerate() {
+        assert_eq!(indexed.at(i), *c);
+    }
+    for i in (0..expected.len()).rev() {
+        assert_eq!(indexed.at(i), expected[i]);
+    }
+    for i in (0..expected.len())
+        .step_by(37)
+        .chain((0..expected.len()).step_by(5))
+    {
+        assert_eq!(indexed.at(i), expected[i]);
+    }
+}
+
+#[test]
+fn range_api() {
+    let sliced = DynamicString::SlicedString {
+        root: Box::new(DynamicString::new("aa0123456789")),
+        start: 2,
+        length: 5,
+    };
+    // 01234-01234😴
+    let str = DynamicString::cons(
+        sliced.clone(),
+        DynamicString::cons(
+            DynamicString::new("-"),
+            DynamicString::cons(sliced, DynamicString::new("😴")),
+        ),
+    );
+    let indexed = IndexedString::new(str.clone());
+
+    assert_eq!(indexed, str);
+    assert_eq!(str, indexed);
+    assert_eq!(indexed.slice(..), str);
+    assert_eq!(indexed.slice(3..8), "34-01");
+    assert_eq!(indexed.slice(11..), "😴");
+    assert_eq!(indexed.slice(20..), "");
+    assert_eq!(
+        indexed.iter_range(4..13).collect::<Vec<u16>>(),
+        str.slice(4, 9).iter().collect::<Vec<u16>>()
+    );
+    assert_eq!(indexed.iter_range(5..5).count(), 0);
+
+    let chunks: Vec<ChunkSlice> = indexed.chunks_in(3..12).collect();
+    assert_eq!(
+        chunks,
+        vec![
+            ChunkSlice::OneByte(b"34"),
+            ChunkSlice::TwoByte(&['-' as u16]),
+            ChunkSlice::OneByte(b"01234"),
+            ChunkSlice::TwoByte(&[0xd83d]),
+        ]
+    );
+
+    assert_eq!(indexed[0], '0' as u16);
+    assert_eq!(indexed[5], '-' as u16);
+    assert_eq!(indexed[12], 0xde34);
+}
+
+#[test]
+fn slice_shares_buffers() {
+    let text = DynamicString::new("0123456789abcdefghijklmnopqrstuvwxyz");
+    let indexed = IndexedString::new(DynamicString::cons(text.clone(), text.clone()));
+    let buffer = match &text {
+        DynamicString::SeqOneByteString(v) => v.as_ptr_range(),
+        _ => panic!("expected a SeqOneByteString"),
+    };
+    let slice = indexed.slice(10..60);
+    assert_eq!(slice, "abcdefghijklmnopqrstuvwxyz0123456789abcdefghijklmn");
+    // Every chunk points into the buffer of the original string instead of a copy.
+    for chunk in slice.chunks() {
+        match chunk {
+            ChunkSlice::OneByte(s) => assert!(buffer.contains(&s.as_ptr())),
+            ChunkSlice::TwoByte(_) => panic!("expected one-byte chunks"),
+        }
+    }
+}
+
+#[test]
+fn slice_many_chunks() {
+    let mut indexed = IndexedString::new(DynamicString::empty());
+    let mut expected = String::new();
+    let pieces = ["0123456789abcdef", "😴"];
+    for i in 0..10_000 {
+        let piece = pieces[i % 2];
+        indexed.append(DynamicString::new(piece));
+        expected.push_str(piece);
+    }
+    let units: Vec<u16> = expected.encode_utf16().collect();
+    let slice = indexed.slice(5..units.len() - 5);
+    assert_eq!(slice.len(), units.len() - 10);
+    assert!(slice.iter().eq(units[5..units.len() - 5].iter().copied()));
+    assert_eq!(slice.char_code_at(50_000), Some(units[50_005]));
+
+    // Short results are flattened like the result of `append`.
+    assert!(matches!(
+        indexed.slice(10..20),
+        DynamicString::SeqTwoByteString(_)
+    ));
+}
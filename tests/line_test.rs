@@ -0,0 +1,53 @@
+use dynstr::{DynamicString, LineIndex};
+
+#[test]
+fn line_terminators() {
+    let str = DynamicString::new("a\nbb\r\nccc\rdd\u{2028}e\u{2029}\n");
+    let index = LineIndex::new(str);
+    assert_eq!(index.line_count(), 7);
+    let lines: Vec<String> = (0..index.line_count())
+        .map(|n| String::from(index.line(n).unwrap()))
+        .collect();
+    assert_eq!(lines, vec!["a", "bb", "ccc", "dd", "e", "", ""]);
+    assert_eq!(index.line(7), None);
+}
+
+#[test]
+fn line_col() {
+    let str = DynamicString::new("ab\r\ncd") + DynamicString::new("\n😴f");
+    let index = LineIndex::new(str);
+    assert_eq!(index.line_col(0), Some((0, 0)));
+    assert_eq!(index.line_col(2), Some((0, 2)));
+    assert_eq!(index.line_col(3), Some((0, 3)));
+    assert_eq!(index.line_col(4), Some((1, 0)));
+    assert_eq!(index.line_col(7), Some((2, 0)));
+    assert_eq!(index.line_col(9), Some((2, 2)));
+    assert_eq!(index.line_col(10), Some((2, 3)));
+    assert_eq!(index.line_col(11), None);
+
+    assert_eq!(index.offset(0, 2), Some(2));
+    assert_eq!(index.offset(0, 3), None);
+    assert_eq!(index.offset(1, 1), Some(5));
+    assert_eq!(index.offset(2, 3), Some(10));
+    assert_eq!(index.offset(2, 4), None);
+    assert_eq!(index.offset(3, 0), None);
+}
+
+#[test]
+fn empty() {
+    let index = LineIndex::new(DynamicString::empty());
+    assert_eq!(index.line_count(), 1);
+    assert_eq!(index.line(0).unwrap(), "");
+    assert_eq!(index.line_col(0), Some((0, 0)));
+    assert_eq!(index.offset(0, 0), Some(0));
+
+    let index = LineIndex::new(DynamicString::new("\n"));
+    assert_eq!(index.line_count(), 2);
+    assert_eq!(index.line(0).unwrap(), "");
+    assert_eq!(index.offset(0, 1), None);
+
+    let index = LineIndex::new(DynamicString::new("\r\n"));
+    assert_eq!(index.line_count(), 2);
+    assert_eq!(index.line(0).unwrap(), "");
+    assert_eq!(index.line(1).unwrap(), "");
+}
@@ -1,4 +1,4 @@
-use dynstr::DynamicString;
+use dynstr::{DynamicString, DynamicStringError};
 
 #[test]
 fn append() {
@@ -34,6 +34,71 @@ fn index_of() {
     );
 }
 
+#[test]
+fn index_of_empty_source() {
+    assert_eq!(DynamicString::empty().index_of("A"), None);
+    assert_eq!(DynamicString::empty().index_of_from("A", 3), None);
+    assert!(!DynamicString::empty().includes("A"));
+    assert!(DynamicString::empty().includes(""));
+}
+
+#[test]
+fn index_of_from() {
+    let str = DynamicString::new("Blue Whale, Killer Whale");
+    assert_eq!(str.index_of_from("Whale", 0), Some(5));
+    assert_eq!(str.index_of_from("Whale", 5), Some(5));
+    assert_eq!(str.index_of_from("Whale", 6), Some(19));
+    assert_eq!(str.index_of_from("Whale", 20), None);
+    assert_eq!(str.index_of_from("", 9), Some(9));
+    assert_eq!(str.index_of_from("", 24), Some(24));
+    assert_eq!(str.index_of_from("", 30), Some(24));
+}
+
+#[test]
+fn last_index_of() {
+    let str = DynamicString::new("canal");
+    assert_eq!(str.last_index_of("a", None), Some(3));
+    assert_eq!(str.last_index_of("a", Some(2)), Some(1));
+    assert_eq!(str.last_index_of("a", Some(0)), None);
+    assert_eq!(str.last_index_of("x", None), None);
+    assert_eq!(str.last_index_of("c", Some(0)), Some(0));
+    assert_eq!(str.last_index_of("", None), Some(5));
+    assert_eq!(str.last_index_of("", Some(2)), Some(2));
+    assert_eq!(str.last_index_of("canals", None), None);
+    assert_eq!(
+        DynamicString::new("aaaa").last_index_of("aa", None),
+        Some(2)
+    );
+    assert_eq!(DynamicString::empty().last_index_of("", None), Some(0));
+    assert_eq!(DynamicString::empty().last_index_of("a", None), None);
+
+    // Matches that span the two halves of a rope.
+    let rope = DynamicString::new("the quick brown ") + "fox jumps over the lazy dog";
+    assert_eq!(rope.last_index_of("the", None), Some(31));
+    assert_eq!(rope.last_index_of("the", Some(30)), Some(0));
+    assert_eq!(rope.last_index_of("n fox", None), Some(14));
+}
+
+#[test]
+fn starts_and_ends_with() {
+    let str = DynamicString::new("To be, or not to be, that is the question.");
+    assert!(str.starts_with("To be"));
+    assert!(!str.starts_with("not to be"));
+    assert!(str.starts_with_at("not to be", 10));
+    assert!(str.starts_with_at("", 42));
+    assert!(str.starts_with_at("", 100));
+    assert!(!str.starts_with_at(".", 100));
+
+    assert!(str.ends_with("question.", None));
+    assert!(!str.ends_with("to be", None));
+    assert!(str.ends_with("to be", Some(19)));
+    assert!(str.ends_with("question.", Some(100)));
+    assert!(str.ends_with("", Some(0)));
+    assert!(!str.ends_with("T", Some(0)));
+    assert!(DynamicString::empty().ends_with("", None));
+    assert!(!DynamicString::empty().ends_with("a", None));
+}
+
 #[test]
 fn split() {
     let source = DynamicString::new("01#-;23#-;45");
@@ -85,3 +150,365 @@ fn split_empty() {
         Vec::<String>::new()
     );
 }
+
+fn split_js(source: &str, separator: &str, limit: Option<usize>) -> Vec<String> {
+    DynamicString::new(source)
+        .split(separator, limit)
+        .iter()
+        .map(String::from)
+        .collect()
+}
+
+#[test]
+fn split_matches_js() {
+    // The expected values are the results of `String.prototype.split` in JavaScript.
+    assert_eq!(split_js("aaaa", "aa", None), vec!["", "", ""]);
+    assert_eq!(split_js("aaa", "aa", None), vec!["", "a"]);
+    assert_eq!(split_js("abab", "ab", None), vec!["", "", ""]);
+    assert_eq!(split_js("a,b,", ",", None), vec!["a", "b", ""]);
+    assert_eq!(split_js(",a", ",", None), vec!["", "a"]);
+    assert_eq!(split_js(",", ",", None), vec!["", ""]);
+    assert_eq!(split_js("a,b,c", ",", Some(2)), vec!["a", "b"]);
+    assert_eq!(split_js("abc", "", Some(2)), vec!["a", "b"]);
+    assert_eq!(split_js("abc", "", None), vec!["a", "b", "c"]);
+    assert_eq!(split_js("", "", None), Vec::<String>::new());
+    assert_eq!(split_js("", "a", None), vec![""]);
+}
+
+#[test]
+fn rsplit() {
+    let rsplit = |source: &str, separator: &str, limit: Option<usize>| -> Vec<String> {
+        DynamicString::new(source)
+            .rsplit(separator, limit)
+            .iter()
+            .map(String::from)
+            .collect()
+    };
+    assert_eq!(rsplit("a,b,c", ",", None), vec!["c", "b", "a"]);
+    assert_eq!(rsplit("a,b,c", ",", Some(2)), vec!["c", "b"]);
+    assert_eq!(rsplit("a,b,", ",", None), vec!["", "b", "a"]);
+    assert_eq!(rsplit("aaaa", "aa", None), vec!["", "", ""]);
+    assert_eq!(rsplit("aaa", "aa", None), vec!["", "a"]);
+    assert_eq!(rsplit("abc", "", None), vec!["c", "b", "a"]);
+    assert_eq!(rsplit("", "", None), Vec::<String>::new());
+    assert_eq!(rsplit("", "a", None), vec![""]);
+    assert_eq!(rsplit("abc", "x", None), vec!["abc"]);
+
+    let rope = DynamicString::new("one fish, two fish, ") + "red fish, blue fish";
+    let (before, after) = rope.rsplit_once(", ").unwrap();
+    assert_eq!(before, "one fish, two fish, red fish");
+    assert_eq!(after, "blue fish");
+    let (before, after) = rope.rsplit_once("").unwrap();
+    assert_eq!(before, rope);
+    assert_eq!(after, "");
+    assert!(rope.rsplit_once("cat").is_none());
+}
+
+#[test]
+fn replace() {
+    let str = DynamicString::new("aaaa");
+    assert_eq!(str.replace("aa", "b"), "baa");
+    assert_eq!(str.replace("x", "b"), "aaaa");
+    assert_eq!(str.replace_all("aa", "b"), "bb");
+    assert_eq!(DynamicString::new("aaa").replace_all("aa", "b"), "ba");
+    assert_eq!(DynamicString::new("abc").replace_all("", "-"), "-a-b-c-");
+    assert_eq!(DynamicString::empty().replace_all("", "x"), "x");
+    assert_eq!(DynamicString::empty().replace_all("a", "x"), "");
+
+    let rope = DynamicString::new("one fish, two fish, ") + "red fish, blue fish";
+    assert_eq!(
+        rope.replace_all("fish", "cat"),
+        "one cat, two cat, red cat, blue cat"
+    );
+    assert_eq!(
+        rope.replace_all(", ", ""),
+        "one fishtwo fishred fishblue fish"
+    );
+}
+
+#[test]
+fn char_boundary() {
+    // 0: a, 1-2: 😴, 3: b, 4-5: 😄
+    let str = DynamicString::new("a😴b😄");
+    let boundaries: Vec<bool> = (0..8).map(|i| str.is_char_boundary(i)).collect();
+    assert_eq!(
+        boundaries,
+        vec![true, true, false, true, true, false, true, false]
+    );
+    assert_eq!(str.floor_char_boundary(2), 1);
+    assert_eq!(str.floor_char_boundary(3), 3);
+    assert_eq!(str.floor_char_boundary(10), 6);
+    assert_eq!(str.ceil_char_boundary(2), 3);
+    assert_eq!(str.ceil_char_boundary(5), 6);
+    assert_eq!(str.ceil_char_boundary(10), 6);
+
+    // A lone trail surrogate is a char boundary.
+    let broken = str.slice(2, 4);
+    assert!(broken.is_char_boundary(1));
+}
+
+#[test]
+fn slice_safe() {
+    let str = DynamicString::new("😴😄😃⛔🎠🚓🚇") + "0123456789";
+    assert_eq!(str.slice_safe(0, 5), "😴😄");
+    assert_eq!(str.slice_safe(1, 5), "😄😃");
+    assert_eq!(str.slice_safe(1, 1), "");
+    assert_eq!(str.slice_safe(11, 100), "🚇0123456789");
+    assert!(str.slice_safe(3, 100).is_well_formed());
+}
+
+#[test]
+fn slice_chars() {
+    let str = DynamicString::new("😴😄😃⛔🎠🚓🚇") + "0123456789";
+    assert_eq!(str.slice_chars(0, 2), "😴😄");
+    assert_eq!(str.slice_chars(3, 2), "⛔🎠");
+    assert_eq!(str.slice_chars(6, 3), "🚇01");
+    assert_eq!(str.slice_chars(15, 10), "89");
+    assert_eq!(str.slice_chars(17, 1), "");
+    assert_eq!(str.slice_chars(0, 0), "");
+}
+
+#[test]
+fn try_slice() {
+    let str = DynamicString::new("0123456789") + "abcdefghij";
+    assert_eq!(str.try_slice(5, 10).unwrap(), "56789abcde");
+    assert_eq!(str.try_slice(20, 0).unwrap(), "");
+    assert_eq!(
+        str.try_slice(21, 0),
+        Err(DynamicStringError::OutOfBounds { index: 21, len: 20 })
+    );
+    assert_eq!(
+        str.try_slice(15, 6),
+        Err(DynamicStringError::OutOfBounds { index: 21, len: 20 })
+    );
+    assert!(str.try_slice(1, usize::MAX).is_err());
+}
+
+#[test]
+fn get() {
+    let str = DynamicString::new("0123456789") + "abcdefghij";
+    assert_eq!(str.get(..).unwrap(), str);
+    assert_eq!(str.get(5..15).unwrap(), "56789abcde");
+    assert_eq!(str.get(18..).unwrap(), "ij");
+    assert_eq!(str.get(..=1).unwrap(), "01");
+    assert_eq!(str.get(20..).unwrap(), "");
+    assert_eq!(
+        str.get(21..),
+        Err(DynamicStringError::OutOfBounds { index: 21, len: 20 })
+    );
+    assert_eq!(
+        str.get(..=20),
+        Err(DynamicStringError::OutOfBounds { index: 21, len: 20 })
+    );
+    #[allow(clippy::reversed_empty_ranges)]
+    let reversed = str.get(5..2);
+    assert_eq!(
+        reversed,
+        Err(DynamicStringError::InvalidRange { start: 5, end: 2 })
+    );
+
+    let emoji = DynamicString::new("😴😄");
+    assert_eq!(emoji.get(2..).unwrap(), "😄");
+    assert_eq!(
+        emoji.get(1..),
+        Err(DynamicStringError::SplitsSurrogate { index: 1 })
+    );
+}
+
+#[test]
+fn substring() {
+    let str = DynamicString::new("0123456789") + "abcdefghij";
+    assert_eq!(str.substring(..), str);
+    assert_eq!(str.substring(2..18), "23456789abcdefgh");
+    assert_eq!(str.substring(..=3), "0123");
+    assert_eq!(str.substring(15..), "fghij");
+    assert_eq!(str.substring(15..100), "fghij");
+    assert_eq!(str.substring(100..), "");
+    assert_eq!(str.substring(5..5), "");
+    #[allow(clippy::reversed_empty_ranges)]
+    let reversed = str.substring(5..2);
+    assert_eq!(reversed, "");
+
+    // Long enough sub-strings are not copied.
+    assert!(matches!(
+        str.substring(2..18),
+        DynamicString::SlicedString { .. }
+    ));
+    assert!(matches!(
+        str.substring(2..5),
+        DynamicString::SeqOneByteString(_)
+    ));
+}
+
+#[test]
+fn split_at() {
+    let str = DynamicString::new("0123456789") + "abcdefghij";
+    let (first, second) = str.split_at(12);
+    assert_eq!(first, "0123456789ab");
+    assert_eq!(second, "cdefghij");
+
+    let (first, second) = str.split_at(0);
+    assert_eq!(first, "");
+    assert_eq!(second, str);
+
+    let (first, second) = str.split_at(30);
+    assert_eq!(first, str);
+    assert_eq!(second, "");
+}
+
+#[test]
+fn char_code_at() {
+    let sliced = DynamicString::SlicedString {
+        root: Box::new(DynamicString::new("aa0123456789")),
+        start: 2,
+        length: 5,
+    };
+    // 01234-01234xyz
+    let str = DynamicString::cons(
+        sliced.clone(),
+        DynamicString::cons(
+            DynamicString::new("-"),
+            DynamicString::cons(sliced, DynamicString::new("xyz")),
+        ),
+    );
+    let expected: Vec<u16> = "01234-01234xyz".encode_utf16().collect();
+    for (i, c) in expected.iter().enumerate() {
+        assert_eq!(str.char_code_at(i), Some(*c));
+    }
+    assert_eq!(str.char_code_at(14), None);
+    assert_eq!(str.slice(4, 8).char_code_at(7), Some('x' as u16));
+    assert_eq!(str.slice(4, 8).char_code_at(8), None);
+
+    assert_eq!(str.at(-1), Some('z' as u16));
+    assert_eq!(str.at(-14), Some('0' as u16));
+    assert_eq!(str.at(-15), None);
+    assert_eq!(DynamicString::empty().at(0), None);
+}
+
+#[test]
+fn char_code_at_after_appends() {
+    // The tree is rebalanced on every concatenation, so each lookup only visits a logarithmic
+    // number of nodes instead of one per append.
+    let mut str = DynamicString::empty();
+    let (mut front, mut back) = (Vec::new(), Vec::new());
+    for i in 0..50_000u32 {
+        let piece = vec![i, i * 7 % 0xd000];
+        let part = DynamicString::from_code_points(piece.clone()).unwrap();
+        if i % 3 == 0 {
+            str = DynamicString::cons(part, str);
+            front.push(piece);
+        } else {
+            str = DynamicString::cons(str, part);
+            back.push(piece);
+        }
+    }
+    let expected: Vec<u16> = front
+        .iter()
+        .rev()
+        .chain(back.iter())
+        .flatten()
+        .map(|&c| c as u16)
+        .collect();
+    assert_eq!(str.len(), expected.len());
+    for (i, c) in expected.iter().enumerate() {
+        assert_eq!(str.char_code_at(i), Some(*c));
+    }
+    assert_eq!(str.char_code_at(expected.len()), None);
+    let slice = str.slice(1_000, 99_000);
+    assert_eq!(slice.char_code_at(0), Some(expected[1_000]));
+}
+
+#[test]
+fn count_and_matches() {
+    let rope = DynamicString::cons(
+        DynamicString::new("one, two, thr"),
+        DynamicString::new("ee, 😴, four"),
+    );
+    assert_eq!(rope.count(", "), 4);
+    assert_eq!(rope.count(['😴']), 1);
+    assert_eq!(rope.count("x"), 0);
+    assert_eq!(DynamicString::new("aaaaa").count("aa"), 2);
+
+    let words: Vec<DynamicString> = rope.matches(|c: u16| c == 'o' as u16).collect();
+    assert_eq!(words, vec!["o", "o", "o"]);
+    let found: Vec<(usize, DynamicString)> = rope.match_indices("three").collect();
+    assert_eq!(found, vec![(10, DynamicString::new("three"))]);
+    let found: Vec<(usize, DynamicString)> = rope.match_indices(['😴', ',']).collect();
+    let indices: Vec<usize> = found.iter().map(|(i, _)| *i).collect();
+    assert_eq!(indices, vec![3, 8, 15, 17, 19]);
+    assert_eq!(found[3].1, "😴");
+}
+
+#[test]
+fn split_helpers() {
+    let str = DynamicString::new("a,b,,c,");
+    assert_eq!(str.splitn(3, ","), vec!["a", "b", ",c,"]);
+    assert_eq!(str.splitn(10, ","), str.split(",", None));
+    assert_eq!(str.split_terminator(","), vec!["a", "b", "", "c"]);
+    assert_eq!(DynamicString::new("abc").splitn(2, ""), vec!["a", "bc"]);
+    assert_eq!(DynamicString::new("ab").splitn(3, ""), vec!["a", "b"]);
+    assert_eq!(
+        DynamicString::new("ab").split_terminator(""),
+        vec!["a", "b"]
+    );
+
+    let (key, value) = str.split_once(",,").unwrap();
+    assert_eq!(
+        (key, value),
+        (DynamicString::new("a,b"), DynamicString::new("c,"))
+    );
+    let (key, value) = str.rsplit_once(',').unwrap();
+    assert_eq!(
+        (key, value),
+        (DynamicString::new("a,b,,c"), DynamicString::empty())
+    );
+
+    let path = DynamicString::new("/usr/local/bin");
+    assert_eq!(path.strip_prefix('/').unwrap(), "usr/local/bin");
+    assert_eq!(path.strip_prefix("").unwrap(), path);
+    assert_eq!(path.strip_suffix("/bin").unwrap(), "/usr/local");
+    assert_eq!(path.strip_suffix(path.clone()).unwrap(), "");
+    assert!(path.strip_suffix("/usr").is_none());
+}
+
+#[test]
+fn split_iter() {
+    let cases = [
+        ("aaaa", "aa"),
+        ("aaa", "aa"),
+        ("a,b,", ","),
+        (",", ","),
+        ("", ""),
+        ("", "a"),
+        ("abc", ""),
+        ("abc", "x"),
+        ("a😴b😴", "😴"),
+    ];
+    for (source, separator) in cases.iter() {
+        let str = DynamicString::new(source);
+        let lazy: Vec<DynamicString> = str.split_iter(*separator).collect();
+        assert_eq!(lazy, str.split(*separator, None), "{:?}", source);
+        let lazy: Vec<DynamicString> = str.rsplit_iter(*separator).collect();
+        assert_eq!(lazy, str.rsplit(*separator, None), "{:?}", source);
+    }
+
+    // Only the part of the string that is needed for the requested fields is searched.
+    let line = DynamicString::new(&"field,".repeat(1000));
+    let mut visited = 0;
+    let fields: Vec<DynamicString> = line
+        .split_iter(|c: u16| {
+            visited += 1;
+            c == ',' as u16
+        })
+        .take(2)
+        .collect();
+    assert_eq!(fields, vec!["field", "field"]);
+    assert_eq!(visited, 12);
+
+    let str = DynamicString::new("a,b");
+    let mut split = str.split_iter(",");
+    assert_eq!(split.next().unwrap(), "a");
+    assert_eq!(split.next().unwrap(), "b");
+    assert!(split.next().is_none());
+    assert!(split.next().is_none());
+}
@@ -0,0 +1,132 @@
+use dynstr::{DynamicString, MatchKind, MultiPattern, MultiPatternFinder};
+
+/// Finds the matches by trying every pattern at every position.
+fn naive(text: &str, patterns: &[&str], kind: MatchKind) -> Vec<(usize, usize)> {
+    let text: Vec<u16> = text.encode_utf16().collect();
+    let patterns: Vec<Vec<u16>> = patterns
+        .iter()
+        .map(|p| p.encode_utf16().collect())
+        .collect();
+    let mut result = Vec::new();
+    let mut i = 0;
+    while i < text.len() {
+        let mut best: Option<usize> = None;
+        for (id, pattern) in patterns.iter().enumerate() {
+            if pattern.is_empty() || !text[i..].starts_with(pattern) {
+                continue;
+            }
+            best = match (best, kind) {
+                (Some(b), MatchKind::LeftmostLongest) if patterns[b].len() >= pattern.len() => {
+                    Some(b)
+                }
+                (Some(b), MatchKind::LeftmostFirst) => Some(b),
+                _ => Some(id),
+            };
+        }
+        match best {
+            Some(id) => {
+                result.push((id, i));
+                i += patterns[id].len();
+            }
+            None => i += 1,
+        }
+    }
+    result
+}
+
+fn find(text: &DynamicString, patterns: &[&str], kind: MatchKind) -> Vec<(usize, usize)> {
+    let patterns: Vec<DynamicString> = patterns.iter().map(|p| DynamicString::new(p)).collect();
+    MultiPatternFinder::new(text, &patterns, kind).collect()
+}
+
+#[test]
+fn basic() {
+    let text = DynamicString::new("abcd");
+    let patterns = ["b", "abcde", "cd", "abc"];
+    assert_eq!(
+        find(&text, &patterns, MatchKind::LeftmostFirst),
+        vec![(3, 0)]
+    );
+    assert_eq!(
+        find(
+            &text,
+            &["b", "abcde", "cd", "abc", "a"],
+            MatchKind::LeftmostFirst
+        ),
+        vec![(3, 0)]
+    );
+    assert_eq!(
+        find(&text, &["a", "abc", "cd"], MatchKind::LeftmostFirst),
+        vec![(0, 0), (2, 2)]
+    );
+    assert_eq!(
+        find(&text, &patterns, MatchKind::LeftmostLongest),
+        vec![(3, 0)]
+    );
+    assert_eq!(
+        find(
+            &DynamicString::new("abcdX"),
+            &["abcde", "ab", "cd"],
+            MatchKind::LeftmostFirst
+        ),
+        vec![(1, 0), (2, 2)]
+    );
+    assert!(find(&text, &[], MatchKind::LeftmostFirst).is_empty());
+    assert!(find(&text, &[""], MatchKind::LeftmostFirst).is_empty());
+    assert!(find(&DynamicString::empty(), &["a"], MatchKind::LeftmostFirst).is_empty());
+}
+
+#[test]
+fn matches_naive() {
+    let mut text = DynamicString::empty();
+    let mut expected = String::new();
+    for i in 0..300 {
+        let piece = ["she", "rs hi", "s hers ", "😴he", "ushe", "r"][i % 6];
+        text = DynamicString::cons(text, DynamicString::new(piece));
+        expected.push_str(piece);
+    }
+
+    let sets: [&[&str]; 4] = [
+        &["he", "she", "his", "hers"],
+        &["s", "she", "shers", "e", "rs"],
+        &["😴", "😴h", "h", "ush", "usher", "hersh"],
+        &["x", "she", "she", "", "r"],
+    ];
+    for patterns in sets.iter() {
+        for &kind in [MatchKind::LeftmostFirst, MatchKind::LeftmostLongest].iter() {
+            assert_eq!(
+                find(&text, patterns, kind),
+                naive(&expected, patterns, kind),
+                "{:?} {:?}",
+                patterns,
+                kind
+            );
+        }
+    }
+}
+
+#[test]
+fn replace_all_multi() {
+    let patterns = [
+        DynamicString::new("&"),
+        DynamicString::new("<"),
+        DynamicString::new(">"),
+        DynamicString::new("\""),
+    ];
+    let replacements = [
+        DynamicString::new("&amp;"),
+        DynamicString::new("&lt;"),
+        DynamicString::new("&gt;"),
+        DynamicString::new("&quot;"),
+    ];
+    let automaton = MultiPattern::new(&patterns, MatchKind::LeftmostFirst);
+    let text = DynamicString::new("<a href=\"x\">") + "Tom & Jerry</a>";
+    assert_eq!(
+        text.replace_all_multi(&automaton, &replacements),
+        "&lt;a href=&quot;x&quot;&gt;Tom &amp; Jerry&lt;/a&gt;"
+    );
+    assert_eq!(
+        DynamicString::new("plain").replace_all_multi(&automaton, &replacements),
+        "plain"
+    );
+}
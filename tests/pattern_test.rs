@@ -1,8 +1,391 @@
-use dynstr::{DynamicString, PatternFinder};
+use std::sync::Arc;
+
+use dynstr::{DynamicString, MatchMode, PatternFinder, ReversePatternFinder, SearchAlgorithm};
 
 #[test]
 fn basic() {
     let text = DynamicString::new("Hello world, I live in a world.");
     let pattern = DynamicString::new("world");
-    assert_eq!(PatternFinder::all(text, pattern), vec![6, 25]);
+    assert_eq!(PatternFinder::all(&text, &pattern), vec![6, 25]);
+}
+
+fn find(text: &str, pattern: &str, mode: MatchMode) -> Vec<usize> {
+    PatternFinder::with_mode(
+        &DynamicString::new(text),
+        &DynamicString::new(pattern),
+        mode,
+    )
+    .collect()
+}
+
+#[test]
+fn modes() {
+    assert_eq!(find("aaaa", "aa", MatchMode::Overlapping), vec![0, 1, 2]);
+    assert_eq!(find("aaaa", "aa", MatchMode::NonOverlapping), vec![0, 2]);
+    assert_eq!(find("aaaaa", "aa", MatchMode::NonOverlapping), vec![0, 2]);
+    assert_eq!(
+        find("abababa", "aba", MatchMode::Overlapping),
+        vec![0, 2, 4]
+    );
+    assert_eq!(
+        find("abababa", "aba", MatchMode::NonOverlapping),
+        vec![0, 4]
+    );
+    assert_eq!(find("aabaab", "aab", MatchMode::NonOverlapping), vec![0, 3]);
+}
+
+#[test]
+fn edge_cases() {
+    for mode in [MatchMode::Overlapping, MatchMode::NonOverlapping] {
+        assert_eq!(find("", "", mode), vec![0]);
+        assert_eq!(find("abc", "", mode), vec![0, 1, 2, 3]);
+        assert!(find("", "a", mode).is_empty());
+        assert!(find("ab", "abc", mode).is_empty());
+        assert_eq!(find("abc", "abc", mode), vec![0]);
+        assert!(find("abc", "abd", mode).is_empty());
+    }
+}
+
+/// Returns the matches of the pattern in the text by comparing every window.
+fn naive(text: &[u16], pattern: &[u16], mode: MatchMode) -> Vec<usize> {
+    let mut result: Vec<usize> = Vec::new();
+    for i in 0..(text.len() + 1).saturating_sub(pattern.len()) {
+        if &text[i..i + pattern.len()] != pattern {
+            continue;
+        }
+        match (mode, result.last()) {
+            (MatchMode::NonOverlapping, Some(&last)) if i < last + pattern.len() => {}
+            _ => result.push(i),
+        }
+    }
+    result
+}
+
+#[test]
+fn matches_across_chunks() {
+    // Build a rope of many small pieces so that matches span several chunks.
+    let mut text = DynamicString::empty();
+    let mut expected = String::new();
+    for i in 0..200 {
+        let piece = if i % 3 == 0 { "ab😴" } else { "aba" };
+        text = DynamicString::cons(text, DynamicString::new(piece));
+        expected.push_str(piece);
+    }
+    let units: Vec<u16> = expected.encode_utf16().collect();
+
+    for pattern in ["aba", "😴ab", "aab", "a😴", "abaaba"] {
+        let ptn: Vec<u16> = pattern.encode_utf16().collect();
+        let naive: Vec<usize> = units
+            .windows(ptn.len())
+            .enumerate()
+            .filter(|(_, w)| *w == ptn.as_slice())
+            .map(|(i, _)| i)
+            .collect();
+        assert_eq!(
+            PatternFinder::all(&text, &DynamicString::new(pattern)),
+            naive,
+            "{}",
+            pattern
+        );
+    }
+
+    let sliced = text.slice(5, 300);
+    let found = PatternFinder::all(&sliced, &DynamicString::new("😴"));
+    let naive: Vec<usize> = (0..300).filter(|&i| units[5 + i] == 0xd83d).collect();
+    assert_eq!(found, naive);
+}
+
+#[test]
+fn algorithms_across_chunks() {
+    // Build a rope of many small pieces so that matches span several chunks.
+    let mut text = DynamicString::empty();
+    let mut expected = String::new();
+    for i in 0..200 {
+        let piece = match i % 5 {
+            0 => "ab😴",
+            1 => "a",
+            2 => "aba",
+            3 => "ba ",
+            _ => "abaabaab",
+        };
+        text = DynamicString::cons(text, DynamicString::new(piece));
+        expected.push_str(piece);
+    }
+    let units: Vec<u16> = expected.encode_utf16().collect();
+    let sliced = text.slice(5, 300);
+
+    let algorithms = [
+        SearchAlgorithm::Kmp,
+        SearchAlgorithm::Horspool,
+        SearchAlgorithm::ByteScan,
+    ];
+    let patterns = [
+        "a",
+        "😴",
+        "b",
+        "aba",
+        "😴ab",
+        "aab",
+        "a😴",
+        "abaaba",
+        "ba ab😴a",
+        "abaabaabab",
+        "x",
+    ];
+    for &algorithm in algorithms.iter() {
+        for &mode in [MatchMode::Overlapping, MatchMode::NonOverlapping].iter() {
+            for pattern in patterns.iter() {
+                let ptn = DynamicString::new(pattern);
+                let ptn_units: Vec<u16> = ptn.iter().collect();
+                let found: Vec<usize> =
+                    PatternFinder::with_algorithm(&text, &ptn, mode, algorithm).collect();
+                assert_eq!(
+                    found,
+                    naive(&units, &ptn_units, mode),
+                    "{:?} {:?} {}",
+                    algorithm,
+                    mode,
+                    pattern
+                );
+
+                let found: Vec<usize> =
+                    PatternFinder::with_algorithm(&sliced, &ptn, mode, algorithm).collect();
+                assert_eq!(
+                    found,
+                    naive(&units[5..305], &ptn_units, mode),
+                    "{:?} {:?} {}",
+                    algorithm,
+                    mode,
+                    pattern
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn select_algorithm() {
+    let text = DynamicString::new("a needle in a haystack");
+    assert_eq!(
+        SearchAlgorithm::select(&text, &DynamicString::new("a")),
+        SearchAlgorithm::ByteScan
+    );
+    assert_eq!(
+        SearchAlgorithm::select(&text, &DynamicString::new("ab")),
+        SearchAlgorithm::Kmp
+    );
+    assert_eq!(
+        SearchAlgorithm::select(&text, &DynamicString::new("needle")),
+        SearchAlgorithm::Horspool
+    );
+}
+
+#[test]
+fn select_threshold() {
+    let text = DynamicString::new("abcdefgh");
+    assert_eq!(
+        SearchAlgorithm::select(&text, &DynamicString::new("abc")),
+        SearchAlgorithm::Kmp
+    );
+    assert_eq!(
+        SearchAlgorithm::select(&text, &DynamicString::new("abcd")),
+        SearchAlgorithm::Horspool
+    );
+}
+
+#[test]
+fn select_by_encoding() {
+    let ascii = DynamicString::new("the quick brown fox");
+    let utf16 = DynamicString::new("Съешь же ещё этих") + ascii.clone();
+    // Only the boundaries of the one-byte chunks can contain a two-byte pattern.
+    assert_eq!(
+        SearchAlgorithm::select(&ascii, &DynamicString::new("oё")),
+        SearchAlgorithm::Horspool
+    );
+    assert_eq!(
+        SearchAlgorithm::select(&utf16, &DynamicString::new("ещё")),
+        SearchAlgorithm::Horspool
+    );
+    assert_eq!(
+        SearchAlgorithm::select(&utf16, &DynamicString::new("fox")),
+        SearchAlgorithm::Horspool
+    );
+    assert_eq!(
+        SearchAlgorithm::select(&utf16, &DynamicString::new("же")),
+        SearchAlgorithm::Kmp
+    );
+    assert_eq!(
+        SearchAlgorithm::select(&utf16, &DynamicString::new("ё")),
+        SearchAlgorithm::ByteScan
+    );
+}
+
+#[test]
+fn long_chunks() {
+    for k in 0..40 {
+        let text = DynamicString::new(&format!("{}yx{}y", "x".repeat(k), "x".repeat(k)));
+        for pattern in ["y", "yx", "xxy", "yxxxxx"].iter() {
+            let ptn = DynamicString::new(pattern);
+            let ptn_units: Vec<u16> = ptn.iter().collect();
+            let units: Vec<u16> = text.iter().collect();
+            assert_eq!(
+                PatternFinder::all(&text, &ptn),
+                naive(&units, &ptn_units, MatchMode::Overlapping),
+                "{} {}",
+                k,
+                pattern
+            );
+        }
+    }
+}
+
+/// Returns the matches of the pattern in the text from the end, by comparing every window.
+fn naive_reverse(text: &[u16], pattern: &[u16], mode: MatchMode) -> Vec<usize> {
+    let mut result: Vec<usize> = Vec::new();
+    for i in (0..(text.len() + 1).saturating_sub(pattern.len())).rev() {
+        if &text[i..i + pattern.len()] != pattern {
+            continue;
+        }
+        match (mode, result.last()) {
+            (MatchMode::NonOverlapping, Some(&last)) if i + pattern.len() > last => {}
+            _ => result.push(i),
+        }
+    }
+    result
+}
+
+#[test]
+fn reverse() {
+    let mut text = DynamicString::empty();
+    let mut expected = String::new();
+    for i in 0..100 {
+        let piece = ["ab😴", "a", "abaabaab", "ba "][i % 4];
+        text = DynamicString::cons(text, DynamicString::new(piece));
+        expected.push_str(piece);
+    }
+    let units: Vec<u16> = expected.encode_utf16().collect();
+    let sliced = text.slice(7, 150);
+
+    for &mode in [MatchMode::Overlapping, MatchMode::NonOverlapping].iter() {
+        for pattern in ["a", "😴", "aba", "😴ab", "abaaba", "ba ab😴a", "x"].iter() {
+            let ptn = DynamicString::new(pattern);
+            let ptn_units: Vec<u16> = ptn.iter().collect();
+            let found: Vec<usize> = ReversePatternFinder::with_mode(&text, &ptn, mode).collect();
+            assert_eq!(
+                found,
+                naive_reverse(&units, &ptn_units, mode),
+                "{}",
+                pattern
+            );
+
+            let found: Vec<usize> = ReversePatternFinder::with_mode(&sliced, &ptn, mode).collect();
+            assert_eq!(
+                found,
+                naive_reverse(&units[7..157], &ptn_units, mode),
+                "{}",
+                pattern
+            );
+        }
+    }
+
+    let abc = DynamicString::new("abc");
+    let empty = DynamicString::empty();
+    let found: Vec<usize> = ReversePatternFinder::new(&abc, &empty).collect();
+    assert_eq!(found, vec![3, 2, 1, 0]);
+    let found: Vec<usize> = ReversePatternFinder::new(&abc, &abc).collect();
+    assert_eq!(found, vec![0]);
+    assert_eq!(ReversePatternFinder::new(&empty, &abc).next(), None);
+}
+
+#[test]
+fn pattern_kinds() {
+    let str = DynamicString::new("one two\tthree, four;😴five");
+    let words =
+        |parts: Vec<DynamicString>| -> Vec<String> { parts.iter().map(String::from).collect() };
+
+    assert_eq!(str.index_of("two"), Some(4));
+    let two = DynamicString::new("two");
+    assert_eq!(str.index_of(&two), Some(4));
+    assert_eq!(str.index_of(two), Some(4));
+    assert_eq!(str.index_of('t'), Some(4));
+    assert_eq!(str.index_of('😴'), Some(20));
+    assert_eq!(str.index_of(0x3b_u16), Some(19));
+    assert_eq!(str.index_of(&[';', ','][..]), Some(13));
+    assert_eq!(str.index_of([';', '😴']), Some(19));
+    let set: &[char; 1] = &['😴'];
+    assert_eq!(str.index_of(set), Some(20));
+    assert_eq!(str.index_of(|c: u16| c == 9), Some(7));
+    assert_eq!(str.index_of(['x']), None);
+
+    assert_eq!(
+        words(str.split(|c: u16| c == 0x20 || c == 9, None)),
+        vec!["one", "two", "three,", "four;😴five"]
+    );
+    assert_eq!(
+        words(str.split([',', ';', '😴'], None)),
+        vec!["one two\tthree", " four", "", "five"]
+    );
+    assert_eq!(
+        words(str.rsplit([' ', '😴'], Some(2))),
+        vec!["five", "four;"]
+    );
+    assert_eq!(str.last_index_of(['o'], None), Some(16));
+    assert_eq!(str.last_index_of(['o'], Some(15)), Some(6));
+    assert_eq!(str.last_index_of('😴', Some(20)), Some(20));
+    assert_eq!(str.last_index_of(['😴'], Some(19)), None);
+    assert!(str.starts_with(|c: u16| c == 'o' as u16));
+    assert!(str.ends_with(['e', 'x'], None));
+    assert!(str.ends_with(['😴'], Some(22)));
+    assert!(!str.ends_with(['😴'], Some(21)));
+    assert!(str.includes(['\t']));
+
+    let mut count = 0;
+    str.split(
+        |c: u16| {
+            count += 1;
+            c == 0
+        },
+        None,
+    );
+    assert_eq!(count, str.len());
+}
+
+#[test]
+fn code_point_sets() {
+    // An unpaired surrogate is never matched by a set of chars.
+    let units = vec![0xd83d, 0x61, 0xd83d, 0xde34, 0xdc00, 0x62];
+    let str = DynamicString::SeqTwoByteString(Arc::new(units));
+    assert_eq!(str.index_of(['😴']), Some(2));
+    assert_eq!(str.index_of(['a', '😴']), Some(1));
+    assert_eq!(str.last_index_of(['😴'], None), Some(2));
+    assert_eq!(str.last_index_of(['b', '😴'], Some(3)), Some(2));
+    assert!(str.starts_with_at(['😴'], 2));
+    assert!(!str.starts_with_at(['😴'], 3));
+    assert!(str.ends_with(['😴'], Some(4)));
+    assert!(!str.ends_with(['😴'], Some(3)));
+
+    let parts: Vec<Vec<u16>> = str
+        .split(['😴'], None)
+        .iter()
+        .map(|part| part.iter().collect())
+        .collect();
+    assert_eq!(parts, vec![vec![0xd83d, 0x61], vec![0xdc00, 0x62]]);
+}
+
+#[test]
+fn trim() {
+    let str = DynamicString::new("\u{feff}\u{3000} \t Hello world!\r\n\u{2028}");
+    assert_eq!(str.trim(), "Hello world!");
+    assert_eq!(str.trim_start(), "Hello world!\r\n\u{2028}");
+    assert_eq!(str.trim_end(), "\u{feff}\u{3000} \t Hello world!");
+    assert_eq!(DynamicString::new(" \n ").trim(), "");
+
+    let str = DynamicString::new("😴😴abc😴");
+    assert_eq!(str.trim_matches('😴'), "abc");
+    assert_eq!(str.trim_matches(['😴']), "abc");
+    assert_eq!(str.trim_start_matches("😴"), "abc😴");
+    assert_eq!(str.trim_end_matches("c😴"), "😴😴ab");
+    assert_eq!(str.trim_matches(""), str);
+    assert_eq!(DynamicString::new("aaa").trim_matches("aa"), "a");
+    assert_eq!(DynamicString::new("aaaa").trim_matches("aa"), "");
 }
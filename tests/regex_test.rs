@@ -0,0 +1,381 @@
+use std::sync::Arc;
+
+use dynstr::{DynamicString, Flags, RegExp, RegExpError};
+
+/// Returns the index of the first match and the captures, or `None` if there is no match.
+fn exec(pattern: &str, flags: &str, text: &str) -> Option<(usize, Vec<Option<String>>)> {
+    let re = RegExp::new(pattern, flags).unwrap();
+    let text = DynamicString::new(text);
+    let captures = re.exec(&text)?;
+    let groups = (0..captures.len())
+        .map(|i| captures.get(i).map(String::from))
+        .collect();
+    Some((captures.start(), groups))
+}
+
+fn groups(values: &[Option<&str>]) -> Vec<Option<String>> {
+    values.iter().map(|v| v.map(String::from)).collect()
+}
+
+fn test(pattern: &str, flags: &str, text: &str) -> bool {
+    RegExp::new(pattern, flags)
+        .unwrap()
+        .is_match(&DynamicString::new(text))
+}
+
+#[test]
+fn spec_examples() {
+    // The examples of the pattern semantics in the ECMAScript specification.
+    assert_eq!(exec("a|ab", "", "abc"), Some((0, groups(&[Some("a")]))));
+    assert_eq!(
+        exec("((a)|(ab))((c)|(bc))", "", "abc"),
+        Some((
+            0,
+            groups(&[
+                Some("abc"),
+                Some("a"),
+                Some("a"),
+                None,
+                Some("bc"),
+                None,
+                Some("bc")
+            ])
+        ))
+    );
+    assert_eq!(
+        exec("a[a-z]{2,4}", "", "abcdefghi"),
+        Some((0, groups(&[Some("abcde")])))
+    );
+    assert_eq!(
+        exec("a[a-z]{2,4}?", "", "abcdefghi"),
+        Some((0, groups(&[Some("abc")])))
+    );
+    assert_eq!(
+        exec("(aa|aabaac|ba|b|c)*", "", "aabaac"),
+        Some((0, groups(&[Some("aaba"), Some("ba")])))
+    );
+    assert_eq!(
+        exec("(z)((a+)?(b+)?(c))*", "", "zaacbbbcac"),
+        Some((
+            0,
+            groups(&[
+                Some("zaacbbbcac"),
+                Some("z"),
+                Some("ac"),
+                Some("a"),
+                None,
+                Some("c")
+            ])
+        ))
+    );
+    assert_eq!(exec("(a*)*", "", "b"), Some((0, groups(&[Some(""), None]))));
+    assert_eq!(
+        exec("(a*)b\\1+", "", "baaaac"),
+        Some((0, groups(&[Some("b"), Some("")])))
+    );
+    assert_eq!(
+        exec("(?=(a+))", "", "baaabac"),
+        Some((1, groups(&[Some(""), Some("aaa")])))
+    );
+    assert_eq!(
+        exec("(?=(a+))a*b\\1", "", "baaabac"),
+        Some((3, groups(&[Some("aba"), Some("a")])))
+    );
+    assert_eq!(
+        exec("(.*?)a(?!(a+)b\\2c)\\2(.*)", "", "baaabaac"),
+        Some((
+            0,
+            groups(&[Some("baaabaac"), Some("ba"), None, Some("abaac")])
+        ))
+    );
+}
+
+#[test]
+fn lookbehind() {
+    assert_eq!(
+        exec("(?<=\\$)\\d+(\\.\\d*)?", "", "cost $10.53"),
+        Some((6, groups(&[Some("10.53"), Some(".53")])))
+    );
+    assert_eq!(
+        exec("(?<!\\$)\\b\\d+", "", "cost $10 or 20"),
+        Some((12, groups(&[Some("20")])))
+    );
+    // Lookbehinds match from right to left, so the greedy group on the right wins.
+    assert_eq!(
+        exec("(?<=(\\d+)(\\d+))$", "", "1053"),
+        Some((4, groups(&[Some(""), Some("1"), Some("053")])))
+    );
+    assert_eq!(exec("(?<=(o)d\\1)r", "", "hodor"), None);
+    assert_eq!(
+        exec("(?<=\\1d(o))r", "", "hodor"),
+        Some((4, groups(&[Some("r"), Some("o")])))
+    );
+}
+
+#[test]
+fn flags() {
+    assert!(test("^hello", "i", "HeLLo world"));
+    assert!(test("[a-z]", "i", "K"));
+    assert!(test("ς", "i", "Σ"));
+    assert!(test("[ς]", "i", "σ"));
+    assert!(test("(a)\\1", "i", "aA"));
+    // Surrogate pairs are compared as code points by the back references in the unicode mode.
+    assert!(test("^(\u{10400})\\1$", "iu", "\u{10400}\u{10428}"));
+    assert!(test("^(\u{10428}a)\\1$", "iu", "\u{10428}a\u{10400}A"));
+    assert!(!test("^(\u{10400})\\1$", "i", "\u{10400}\u{10428}"));
+    assert!(test("(?<=\\1(\u{10400}))$", "iu", "\u{10428}\u{10400}"));
+    // The non-ASCII characters only match ASCII ones with simple case folding.
+    assert!(!test("ſ", "i", "s"));
+    assert!(test("ſ", "iu", "S"));
+    assert!(!test("\\u212a", "i", "k"));
+    assert!(test("\\u212a", "iu", "k"));
+    assert!(test("\u{ab70}", "iu", "\u{13a0}"));
+    assert!(test("\u{10400}", "iu", "\u{10428}"));
+
+    assert_eq!(exec("^b", "", "a\nb"), None);
+    assert_eq!(exec("^b", "m", "a\nb"), Some((2, groups(&[Some("b")]))));
+    assert_eq!(exec("a$", "m", "a\r\nb"), Some((0, groups(&[Some("a")]))));
+    assert!(!test("a.b", "", "a\nb"));
+    assert!(test("a.b", "s", "a\u{2028}b"));
+
+    assert!(test("^.$", "u", "😴"));
+    assert!(!test("^.$", "", "😴"));
+    assert!(test("^[😴]$", "u", "😴"));
+    assert!(test("^\\u{1F634}{2}$", "u", "😴😴"));
+    assert!(test("^\\ud83d\\ude34$", "u", "😴"));
+    assert!(!test("\\ud83d", "u", "😴"));
+    assert!(test("\\ud83d", "", "😴"));
+    // Without the `u` flag a quantifier only repeats the trail surrogate.
+    let units = DynamicString::SeqTwoByteString(Arc::new(vec![0xd83d, 0xde34, 0xde34]));
+    assert!(RegExp::new("^😴{2}$", "").unwrap().is_match(&units));
+    assert!(!RegExp::new("^😴{2}$", "u").unwrap().is_match(&units));
+
+    let re = RegExp::new("a", "y").unwrap();
+    let text = DynamicString::new("aab");
+    assert!(re.exec_at(&text, 1).is_some());
+    assert!(re.exec_at(&text, 2).is_none());
+    let all: Vec<usize> = re.captures_iter(&text).map(|c| c.start()).collect();
+    assert_eq!(all, vec![0, 1]);
+
+    let flags = Flags::parse("yusmig").unwrap();
+    assert_eq!(flags.to_string(), "gimsuy");
+    assert_eq!(RegExp::new("", "g").unwrap().to_string(), "/(?:)/g");
+    assert_eq!(RegExp::new("a+", "").unwrap().to_string(), "/a+/");
+}
+
+#[test]
+fn escapes_and_classes() {
+    assert!(test("^\\d\\D\\w\\W\\s\\S$", "", "1a_ \u{3000}x"));
+    assert!(test("^\\bab\\B", "", "abc"));
+    assert!(test("^[^abc]$", "", "d"));
+    assert!(!test("^[^abc]$", "", "b"));
+    assert!(test("^[\\d-z]+$", "", "1-z"));
+    assert!(test("\\101\\x42\\u0043\\cJ", "", "ABC\n"));
+    assert!(test("^\\c$", "", "\\c"));
+    assert!(test("^a{$", "", "a{"));
+    assert!(test("^x{1,$", "", "x{1,"));
+    assert!(test("^]}$", "", "]}"));
+    assert!(test("^\\2(a)$", "", "\u{2}a"));
+    assert!(test("^[\\b]$", "", "\u{8}"));
+    assert!(test("^(?<first>a)\\k<first>$", "", "aa"));
+    assert!(test("^\\k<a>$", "", "k<a>"));
+    assert!(test("a|", "", "b"));
+    assert!(test("^(?:ab)+$", "", "ababab"));
+}
+
+#[test]
+fn errors() {
+    let error = |pattern: &str, flags: &str| RegExp::new(pattern, flags).map(|_| ()).unwrap_err();
+    assert_eq!(error("a", "gx"), RegExpError::InvalidFlag { flag: 'x' });
+    assert_eq!(error("a", "gg"), RegExpError::InvalidFlag { flag: 'g' });
+    assert_eq!(error("(a", ""), RegExpError::UnexpectedEnd);
+    assert_eq!(error("a\\", ""), RegExpError::UnexpectedEnd);
+    assert_eq!(
+        error("a)", ""),
+        RegExpError::UnexpectedChar { index: 1, c: ')' }
+    );
+    assert_eq!(error("*a", ""), RegExpError::NothingToRepeat { index: 0 });
+    assert_eq!(error("a**", ""), RegExpError::NothingToRepeat { index: 2 });
+    assert_eq!(
+        error("a{2,1}", ""),
+        RegExpError::InvalidQuantifier { index: 0 }
+    );
+    assert_eq!(
+        error("[z-a]", ""),
+        RegExpError::InvalidClassRange { index: 1 }
+    );
+    assert_eq!(
+        error("(?<a>.)(?<a>.)", ""),
+        RegExpError::InvalidGroupName { index: 9 }
+    );
+    assert_eq!(
+        error("\\k<x>(?<y>.)", ""),
+        RegExpError::InvalidBackReference { index: 0 }
+    );
+    assert_eq!(
+        error("\\2(a)", "u"),
+        RegExpError::InvalidBackReference { index: 0 }
+    );
+    assert_eq!(error("\\q", "u"), RegExpError::InvalidEscape { index: 0 });
+    assert_eq!(
+        error("a{", "u"),
+        RegExpError::InvalidQuantifier { index: 1 }
+    );
+    assert_eq!(
+        error("[\\d-z]", "u"),
+        RegExpError::InvalidClassRange { index: 1 }
+    );
+    assert_eq!(error("a{2000000}", ""), RegExpError::TooLarge);
+    // The size is checked before the iterations are unrolled.
+    assert_eq!(error("a{0,4294967295}", ""), RegExpError::TooLarge);
+    assert_eq!(error("(?:a{1000}){4294967295}", ""), RegExpError::TooLarge);
+    assert_eq!(error("(a|b){1000,4294967295}", ""), RegExpError::TooLarge);
+    // An atom that compiles to nothing is not repeated.
+    assert!(test("^(?:){4294967295}$", "", ""));
+    assert!(test("^a(?:){2,4294967295}b$", "", "ab"));
+    assert!(test("^a(?:)*?b$", "", "ab"));
+
+    // The nesting depth is limited instead of overflowing the stack.
+    let nested =
+        |depth: usize, open: &str, close: &str| open.repeat(depth) + "a" + &close.repeat(depth);
+    assert_eq!(error(&nested(200_000, "(", ")"), ""), RegExpError::TooLarge);
+    assert_eq!(error(&nested(257, "(?=", ")"), ""), RegExpError::TooLarge);
+    assert!(test(&nested(256, "(?:", ")*"), "", "aaa"));
+    assert!(test(&nested(256, "(?=", ")"), "u", "a"));
+}
+
+#[test]
+fn replace() {
+    let replace = |pattern: &str, flags: &str, text: &str, replacement: &str| {
+        let re = RegExp::new(pattern, flags).unwrap();
+        String::from(re.replace(&DynamicString::new(text), replacement))
+    };
+    assert_eq!(
+        replace("(\\w+)\\s(\\w+)", "", "John Smith", "$2, $1"),
+        "Smith, John"
+    );
+    assert_eq!(replace("b", "", "abc", "[$`|$&|$']"), "a[a|b|c]c");
+    assert_eq!(replace("b", "g", "abcb", "$$"), "a$c$");
+    assert_eq!(replace("(b)", "", "abc", "$10$2$"), "ab0$2$c");
+    assert_eq!(replace("(?<x>b)", "", "abc", "<$<x>$<y>>"), "a<b>c");
+    assert_eq!(replace("(b)", "", "abc", "$<x>"), "a$<x>c");
+    assert_eq!(replace("", "g", "abc", "-"), "-a-b-c-");
+    assert_eq!(replace("x*", "g", "abc", "-"), "-a-b-c-");
+    assert_eq!(replace("a", "", "aaa", "b"), "baa");
+}
+
+#[test]
+fn split() {
+    let split = |pattern: &str, text: &str, limit: Option<usize>| -> Vec<Option<String>> {
+        let re = RegExp::new(pattern, "").unwrap();
+        re.split(&DynamicString::new(text), limit)
+            .into_iter()
+            .map(|part| part.map(String::from))
+            .collect()
+    };
+    assert_eq!(
+        split("\\d", "a1b2c3", None),
+        groups(&[Some("a"), Some("b"), Some("c"), Some("")])
+    );
+    assert_eq!(
+        split("(?:)", "abc", None),
+        groups(&[Some("a"), Some("b"), Some("c")])
+    );
+    assert_eq!(split("a", "", None), groups(&[Some("")]));
+    assert!(split("(?:)", "", None).is_empty());
+    assert_eq!(
+        split("\\d", "a1b2c3", Some(2)),
+        groups(&[Some("a"), Some("b")])
+    );
+    assert_eq!(
+        split(
+            "<(\\/)?([^<>]+)>",
+            "A<B>bold</B>and<CODE>coded</CODE>",
+            None
+        ),
+        groups(&[
+            Some("A"),
+            None,
+            Some("B"),
+            Some("bold"),
+            Some("/"),
+            Some("B"),
+            Some("and"),
+            None,
+            Some("CODE"),
+            Some("coded"),
+            Some("/"),
+            Some("CODE"),
+            Some(""),
+        ])
+    );
+}
+
+#[test]
+fn ropes() {
+    // Matches, captures and assertions span the chunks of the rope.
+    let pieces = [
+        "The qu",
+        "ick br",
+        "o",
+        "wn 😴",
+        "fox ",
+        "jum",
+        "ps over 12",
+        "34 dogs",
+    ];
+    let mut text = DynamicString::empty();
+    let mut flat = String::new();
+    for piece in pieces.iter() {
+        text = DynamicString::cons(text, DynamicString::new(piece));
+        flat.push_str(piece);
+    }
+    let flat = DynamicString::new(&flat);
+    let patterns = [
+        ("q[a-z]+\\s(b\\w+)", ""),
+        ("\\b(\\w)(\\w)\\2?", "g"),
+        ("(?<=😴)\\s*(\\w+)", "u"),
+        ("\\d+", "g"),
+        ("(?<!\\d)\\d{2}", "g"),
+        ("O\\w+", "gi"),
+        ("^.*$", ""),
+    ];
+    for (pattern, flags) in patterns.iter() {
+        let re = RegExp::new(pattern, flags).unwrap();
+        let on_rope: Vec<(usize, Vec<Option<String>>)> = re
+            .captures_iter(&text)
+            .map(|c| {
+                (
+                    c.start(),
+                    (0..c.len()).map(|i| c.get(i).map(String::from)).collect(),
+                )
+            })
+            .collect();
+        let on_flat: Vec<(usize, Vec<Option<String>>)> = re
+            .captures_iter(&flat)
+            .map(|c| {
+                (
+                    c.start(),
+                    (0..c.len()).map(|i| c.get(i).map(String::from)).collect(),
+                )
+            })
+            .collect();
+        assert!(!on_rope.is_empty(), "{}", pattern);
+        assert_eq!(on_rope, on_flat, "{}", pattern);
+    }
+
+    let re = RegExp::new("(?<=😴)\\s*(\\w+)", "u").unwrap();
+    assert_eq!(re.exec(&text).unwrap().get(1).unwrap(), "fox");
+    let re = RegExp::new("\\d+", "").unwrap();
+    assert_eq!(re.exec(&text).unwrap().range(0), Some(33..37));
+}
+
+#[test]
+fn long_inputs() {
+    // The matcher does not recurse on the repetitions, so long matches are fine.
+    let text = DynamicString::new(&"ab".repeat(50_000));
+    let re = RegExp::new("^(?:a|b)*$", "").unwrap();
+    assert!(re.is_match(&text));
+    let re = RegExp::new("(ab)+?$", "").unwrap();
+    assert_eq!(re.exec(&text).unwrap().get(1).unwrap(), "ab");
+}
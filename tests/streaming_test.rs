@@ -0,0 +1,60 @@
+use dynstr::{DynamicString, MatchMode, PatternFinder, StreamingFinder};
+
+#[test]
+fn matches_whole_text() {
+    let pieces = [
+        "ab",
+        "a",
+        "",
+        "baab😴",
+        "abab",
+        "a",
+        "b",
+        "aab😴ab",
+        "😴",
+        "abaabaabab",
+        "x",
+    ];
+    let mut text = DynamicString::empty();
+    for &mode in [MatchMode::Overlapping, MatchMode::NonOverlapping].iter() {
+        for pattern in ["a", "ab", "aba", "😴ab", "abaab", "b😴a", "", "x", "y"].iter() {
+            let ptn = DynamicString::new(pattern);
+            let mut finder = StreamingFinder::with_mode(&ptn, mode);
+            let mut found = Vec::new();
+            text = DynamicString::empty();
+            for piece in pieces.iter() {
+                let piece = DynamicString::new(piece);
+                found.extend(finder.feed(&piece));
+                text = text + piece;
+            }
+            let expected: Vec<usize> = PatternFinder::with_mode(&text, &ptn, mode).collect();
+            assert_eq!(found, expected, "{:?} {}", mode, pattern);
+            assert_eq!(finder.offset(), text.len());
+        }
+    }
+    assert_eq!(text.len(), 35);
+}
+
+#[test]
+fn ropes_and_reset() {
+    let pattern = DynamicString::new("needle");
+    let mut finder = StreamingFinder::new(&pattern);
+    let rope = DynamicString::cons(
+        DynamicString::new("a ne"),
+        DynamicString::cons(
+            DynamicString::new("ed"),
+            DynamicString::new("le in a hay ne"),
+        ),
+    );
+    assert_eq!(finder.feed(&rope), vec![2]);
+    assert_eq!(finder.feed(&DynamicString::new("edle")), vec![18]);
+
+    finder.reset();
+    assert_eq!(finder.offset(), 0);
+    assert_eq!(finder.feed(&DynamicString::new("dle needle")), vec![4]);
+
+    let mut empty = StreamingFinder::new(&DynamicString::empty());
+    assert_eq!(empty.feed(&DynamicString::empty()), vec![0]);
+    assert_eq!(empty.feed(&DynamicString::new("ab")), vec![1, 2]);
+    assert_eq!(empty.feed(&DynamicString::empty()), vec![]);
+}
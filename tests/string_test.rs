@@ -1,4 +1,4 @@
-use dynstr::DynamicString;
+use dynstr::{ChunkSlice, DynamicString};
 
 #[test]
 fn basic() {
@@ -27,10 +27,7 @@ fn unicode() {
 #[test]
 fn iter_cons() {
     let simple = Box::new(DynamicString::new("012345"));
-    let cons = DynamicString::ConsString {
-        first: simple.clone(),
-        second: simple.clone(),
-    };
+    let cons = DynamicString::cons(*simple.clone(), *simple.clone());
     assert_eq!(&String::from(cons), "012345012345");
 }
 
@@ -61,10 +58,10 @@ fn iter_slice() {
 
 #[test]
 fn iter_cons_slice() {
-    let cons = Box::new(DynamicString::ConsString {
-        first: Box::new(DynamicString::new("012345")),
-        second: Box::new(DynamicString::new("6789a")),
-    });
+    let cons = Box::new(DynamicString::cons(
+        DynamicString::new("012345"),
+        DynamicString::new("6789a"),
+    ));
 
     // All in first half.
     let slice = DynamicString::SlicedString {
@@ -128,13 +125,10 @@ fn iter_cons_slice() {
 
 #[test]
 fn iter_cons_cons_slice() {
-    let cons = DynamicString::ConsString {
-        first: Box::new(DynamicString::new("012")),
-        second: Box::new(DynamicString::ConsString {
-            first: Box::new(DynamicString::new("345")),
-            second: Box::new(DynamicString::new("678")),
-        }),
-    };
+    let cons = DynamicString::cons(
+        DynamicString::new("012"),
+        DynamicString::cons(DynamicString::new("345"), DynamicString::new("678")),
+    );
 
     assert_eq!(&String::from(cons.clone()), "012345678");
     let root = Box::new(cons);
@@ -261,19 +255,13 @@ fn nth() {
 
         let space = Box::new(DynamicString::new(" "));
 
-        DynamicString::ConsString {
-            first: computer,
-            second: Box::new(DynamicString::ConsString {
-                first: space.clone(),
-                second: Box::new(DynamicString::ConsString {
-                    first: is,
-                    second: Box::new(DynamicString::ConsString {
-                        first: space.clone(),
-                        second: theory,
-                    }),
-                }),
-            }),
-        }
+        DynamicString::cons(
+            *computer,
+            DynamicString::cons(
+                *space.clone(),
+                DynamicString::cons(*is, DynamicString::cons(*space.clone(), *theory)),
+            ),
+        )
     };
 
     assert_eq!(&String::from(sentence.clone()), "computer is theory");
@@ -384,3 +372,28 @@ fn eq() {
         DynamicString::new("😴")
     );
 }
+
+#[test]
+fn chunks() {
+    let str = DynamicString::new("Hello, ") + DynamicString::new("سلام دنیا") + "!";
+    let chunks: Vec<ChunkSlice> = str.chunks().collect();
+    assert_eq!(chunks.len(), 3);
+    assert_eq!(chunks[0], ChunkSlice::OneByte(b"Hello, "));
+    assert_eq!(chunks[2], ChunkSlice::OneByte(b"!"));
+
+    let long =
+        DynamicString::new("The quick brown fox ") + DynamicString::new("jumps over the lazy dog");
+    let sliced = long.slice(10, 20);
+    let units: Vec<u16> = sliced
+        .chunks()
+        .flat_map(|chunk| (0..chunk.len()).map(move |i| chunk.get(i).unwrap()))
+        .collect();
+    assert_eq!(units, sliced.iter().collect::<Vec<u16>>());
+    assert_eq!(sliced.chunks().count(), 2);
+
+    assert_eq!(DynamicString::empty().chunks().count(), 0);
+
+    let mut reversed: Vec<ChunkSlice> = sliced.rchunks().collect();
+    reversed.reverse();
+    assert_eq!(reversed, sliced.chunks().collect::<Vec<ChunkSlice>>());
+}
@@ -0,0 +1,153 @@
+use dynstr::{
+    CodePointMetric, DynamicString, LineIndex, LinesMetric, Summary, Utf16Metric, Utf8Metric,
+};
+
+fn build(parts: &[&str]) -> DynamicString {
+    parts.iter().fold(DynamicString::empty(), |acc, part| {
+        DynamicString::cons(acc, DynamicString::new(part))
+    })
+}
+
+#[test]
+fn summary_across_boundaries() {
+    let surrogates: Vec<u16> = "😴".encode_utf16().collect();
+    let str = DynamicString::cons(
+        DynamicString::cons(
+            DynamicString::new("ab\r"),
+            DynamicString::SingleTwoByteChar(surrogates[0]),
+        ),
+        DynamicString::cons(
+            DynamicString::SingleTwoByteChar(surrogates[1]),
+            DynamicString::new("\n"),
+        ),
+    );
+    let expected = Summary::from_units(str.iter());
+    assert_eq!(str.summary(), expected);
+    assert_eq!(expected.len(), 6);
+    assert_eq!(expected.lines(), 2);
+    assert_eq!(expected.code_points(), 5);
+    assert_eq!(expected.utf8_len(), 8);
+
+    let crlf = DynamicString::cons(DynamicString::new("a\r"), DynamicString::new("\nb"));
+    assert_eq!(crlf.summary().lines(), 1);
+    assert_eq!(crlf.summary(), Summary::from_units(crlf.iter()));
+}
+
+#[test]
+fn measure_edited_rope() {
+    let text = "first line\r\nsecond 😴 line\nthird line with some more text\u{2028}last ⛔";
+    let str = build(&[
+        "first line\r",
+        "\nsecond 😴",
+        " line\nthird line with",
+        " some more text",
+    ]);
+    let str = str.slice(0, str.len()) + "\u{2028}last ⛔";
+    let edited = str.slice(0, 20) + str.slice(20, str.len() - 20);
+    assert_eq!(edited, text);
+
+    let units: Vec<u16> = text.encode_utf16().collect();
+    for offset in 0..=units.len() {
+        let expected = Summary::from_units(units[..offset].iter().copied());
+        assert_eq!(edited.measure::<Utf16Metric>(offset), expected.len());
+        assert_eq!(edited.measure::<LinesMetric>(offset), expected.lines());
+        assert_eq!(
+            edited.measure::<CodePointMetric>(offset),
+            expected.code_points()
+        );
+        assert_eq!(edited.measure::<Utf8Metric>(offset), expected.utf8_len());
+    }
+    assert_eq!(edited.measure::<Utf8Metric>(1000), text.len());
+
+    // The inverse finds the first offset that reaches the measure.
+    for target in 0..=text.len() + 1 {
+        let expected = (0..=units.len()).find(|&offset| {
+            Summary::from_units(units[..offset].iter().copied()).utf8_len() >= target
+        });
+        assert_eq!(edited.offset_of::<Utf8Metric>(target), expected);
+    }
+    assert_eq!(edited.offset_of::<CodePointMetric>(19), Some(19));
+    assert_eq!(edited.offset_of::<CodePointMetric>(20), Some(20));
+    assert_eq!(edited.offset_of::<LinesMetric>(1), Some(11));
+    assert_eq!(edited.offset_of::<LinesMetric>(4), None);
+}
+
+#[test]
+fn line_col_matches_line_index() {
+    let str = build(&["ab\r", "\ncd\n", "\r\r\n", "😴f\u{2029}", "", "\n"]);
+    let index = LineIndex::new(str.clone());
+    for offset in 0..=str.len() + 1 {
+        assert_eq!(
+            str.line_col(offset),
+            index.line_col(offset),
+            "offset {}",
+            offset
+        );
+    }
+    for line in 0..=index.line_count() {
+        assert_eq!(str.line_start(line), index.offset(line, 0), "line {}", line);
+    }
+}
+
+#[test]
+fn empty() {
+    let str = DynamicString::empty();
+    assert!(str.summary().is_empty());
+    assert_eq!(str.measure::<LinesMetric>(0), 0);
+    assert_eq!(str.offset_of::<Utf8Metric>(0), Some(0));
+    assert_eq!(str.offset_of::<Utf8Metric>(1), None);
+    assert_eq!(str.line_col(0), Some((0, 0)));
+    assert_eq!(str.line_start(0), Some(0));
+    assert_eq!(str.line_start(1), None);
+}
+
+#[test]
+fn summary_after_appends() {
+    let mut str = DynamicString::new("line 1\r");
+    assert_eq!(str.summary().lines(), 1);
+    for i in 0..5_000 {
+        str = DynamicString::cons(
+            str,
+            DynamicString::new(if i % 2 == 0 { "\n" } else { "x\r" }),
+        );
+        if i == 100 {
+            // The summary of the earlier nodes is reused by the later ones.
+            assert_eq!(str.summary().lines(), 51);
+        }
+    }
+    assert_eq!(str.summary().lines(), 2_501);
+    assert_eq!(str.summary().len(), 7_507);
+}
+
+#[test]
+fn queries_on_deep_rope() {
+    // The queries descend the rope that is rebalanced after every append.
+    let mut str = DynamicString::empty();
+    let mut text = String::new();
+    for i in 0..5_000 {
+        let piece = if i % 3 == 0 { "a\r" } else { "\nb😴" };
+        str = DynamicString::cons(str, DynamicString::new(piece));
+        text.push_str(piece);
+    }
+    let units: Vec<u16> = text.encode_utf16().collect();
+
+    for &offset in [0, 1, 7_777, units.len() / 2, units.len()].iter() {
+        let expected = Summary::from_units(units[..offset].iter().copied());
+        assert_eq!(str.measure::<LinesMetric>(offset), expected.lines());
+        assert_eq!(str.measure::<Utf8Metric>(offset), expected.utf8_len());
+    }
+    let lines = Summary::from_units(units.iter().copied()).lines();
+    let last_line = (0..units.len())
+        .rev()
+        .find(|&i| {
+            units[i] == '\r' as u16 || (units[i] == '\n' as u16 && units[i - 1] != '\r' as u16)
+        })
+        .unwrap();
+    assert_eq!(str.offset_of::<LinesMetric>(lines), Some(last_line + 1));
+    assert_eq!(str.offset_of::<LinesMetric>(lines + 1), None);
+    // The last code point is counted on the lead surrogate of 😴.
+    assert_eq!(
+        str.offset_of::<CodePointMetric>(text.chars().count()),
+        Some(units.len() - 1)
+    );
+}
@@ -0,0 +1,53 @@
+use dynstr::{DynamicString, Utf8Index};
+
+#[test]
+fn utf8_len() {
+    assert_eq!(DynamicString::empty().utf8_len(), 0);
+    assert_eq!(DynamicString::new("Hello").utf8_len(), 5);
+    let str = DynamicString::new("😴😄😃⛔🎠🚓🚇") + "سلام" + "0123456789";
+    assert_eq!(str.utf8_len(), String::from(&str).len());
+    // Unpaired surrogates take three bytes.
+    assert_eq!(str.slice(1, 5).utf8_len(), 3 + 4 + 4);
+    assert_eq!(str.slice(1, 4).utf8_len(), 3 + 4 + 3);
+}
+
+#[test]
+fn offsets() {
+    let text = "Hello, 😴 wörld! ".repeat(20) + &"سلام دنیا ".repeat(20);
+    let str = DynamicString::cons(
+        DynamicString::new(&text).slice(0, 150),
+        DynamicString::new(&text).slice(150, 1000),
+    );
+    let index = Utf8Index::new(str.clone());
+    assert_eq!(index.utf8_len(), text.len());
+    assert_eq!(index.utf16_len(), str.len());
+
+    let mut unit = 0;
+    for (byte, c) in text.char_indices() {
+        assert_eq!(index.utf8_offset(unit), Some(byte));
+        assert_eq!(index.utf16_offset(byte), Some(unit));
+        // Offsets inside of a character are moved to its start.
+        for i in 1..c.len_utf8() {
+            assert_eq!(index.utf16_offset(byte + i), Some(unit));
+        }
+        if c.len_utf16() == 2 {
+            assert_eq!(index.utf8_offset(unit + 1), Some(byte));
+        }
+        unit += c.len_utf16();
+    }
+    assert_eq!(index.utf8_offset(str.len()), Some(text.len()));
+    assert_eq!(index.utf16_offset(text.len()), Some(str.len()));
+    assert_eq!(index.utf8_offset(str.len() + 1), None);
+    assert_eq!(index.utf16_offset(text.len() + 1), None);
+}
+
+#[test]
+fn latin1() {
+    // One-byte strings may contain characters that take two bytes in UTF-8.
+    let str = DynamicString::from_utf16(&[0x61, 0xe9, 0x62]).unwrap();
+    assert!(str.has_one_byte_char());
+    let index = Utf8Index::new(str);
+    assert_eq!(index.utf8_len(), 4);
+    assert_eq!(index.utf8_offset(2), Some(3));
+    assert_eq!(index.utf16_offset(2), Some(1));
+}